@@ -0,0 +1,149 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::runtime::Handle;
+
+use crate::*;
+
+/// Async front-end for [`ZipFile`]. Every libzip call, including the commit
+/// done by `zip_close`, runs on tokio's blocking pool so it never stalls the
+/// runtime. That includes the drop policy of the wrapped archive, which runs
+/// there once the last handle is dropped.
+#[derive(Clone, Debug)]
+pub struct AsyncZipFile {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    // Only `None` while `Inner` is being dropped.
+    zip: Mutex<Option<ZipFile>>,
+    filename: PathBuf,
+}
+
+impl AsyncZipFile {
+    pub async fn open(file: &Path, create: bool) -> ZipResult<Self> {
+        let file = file.to_path_buf();
+        let zip = spawn(move || ZipFile::open(&file, create)).await?;
+
+        Ok(Self::from(zip))
+    }
+
+    /// Adds an entry whose data is pulled from `reader` while the archive is
+    /// being closed.
+    pub async fn add_reader(
+        &self,
+        reader: impl AsyncRead + Unpin + Send + 'static,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        let reader = BlockingReader {
+            reader,
+            handle: Handle::current(),
+        };
+        let filename = filename.to_string();
+        let options = options.clone();
+
+        self.with_zip(move |zip| zip.add_reader(reader, &filename, &options))
+            .await
+    }
+
+    pub async fn close(&self) -> ZipResult<()> {
+        self.with_zip(|zip| zip.close()).await
+    }
+
+    pub async fn entries(&self) -> ZipResult<Vec<EntryInfo>> {
+        self.with_zip(|zip| zip.iter().collect()).await
+    }
+
+    pub async fn extract_all(&self, dest: &Path, options: &ExtractOptions) -> ZipResult<()> {
+        let dest = dest.to_path_buf();
+        let options = *options;
+
+        self.with_zip(move |zip| zip.extract_all(&dest, &options))
+            .await
+    }
+
+    pub async fn extract_to(&self, entry_name: &str, dest: &Path) -> ZipResult<u64> {
+        let entry_name = entry_name.to_string();
+        let dest = dest.to_path_buf();
+
+        self.with_zip(move |zip| zip.extract_to(&entry_name, &dest))
+            .await
+    }
+
+    /// Runs `f` against the wrapped archive on the blocking pool.
+    pub async fn with_zip<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut ZipFile) -> ZipResult<T> + Send + 'static,
+    ) -> ZipResult<T> {
+        let inner = self.inner.clone();
+
+        spawn(move || {
+            let mut zip = inner.zip.lock().unwrap_or_else(|error| error.into_inner());
+            f(zip.as_mut().ok_or(ZipError::NotOpen)?)
+        })
+        .await
+    }
+
+    pub fn filename(&self) -> &Path {
+        &self.inner.filename
+    }
+}
+
+impl From<ZipFile> for AsyncZipFile {
+    fn from(zip: ZipFile) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                filename: zip.filename().to_path_buf(),
+                zip: Mutex::new(Some(zip)),
+            }),
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        let zip = self
+            .zip
+            .get_mut()
+            .unwrap_or_else(|error| error.into_inner());
+        let Some(zip) = zip.take() else {
+            return;
+        };
+
+        // Dropping may commit with `zip_close`, which blocks and may pull from an
+        // `add_reader` source that needs the runtime, so it cannot run on a worker.
+        match Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || drop(zip));
+            }
+            Err(_) => drop(zip),
+        }
+    }
+}
+
+async fn spawn<T: Send + 'static>(
+    f: impl FnOnce() -> ZipResult<T> + Send + 'static,
+) -> ZipResult<T> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(error) => Err(ZipError::Io(io::Error::other(error))),
+    }
+}
+
+/// Bridges an [`AsyncRead`] into the synchronous [`Read`] libzip pulls from.
+/// It is only ever read from the blocking pool, where blocking on the runtime
+/// is allowed.
+struct BlockingReader<R> {
+    reader: R,
+    handle: Handle,
+}
+
+impl<R: AsyncRead + Unpin> Read for BlockingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.handle.block_on(self.reader.read(buf))
+    }
+}
@@ -0,0 +1,79 @@
+use crate::*;
+
+/// A staged edit that [`ZipFile::close`] would write, as reported by
+/// [`ZipFile::pending_changes`]. An entry that was both renamed and replaced is
+/// reported once for each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PendingChange {
+    Added {
+        index: u64,
+        name: String,
+    },
+    /// The entry's data comes from a new source.
+    Replaced {
+        index: u64,
+        name: String,
+    },
+    Renamed {
+        index: u64,
+        from: String,
+        to: String,
+    },
+    Deleted {
+        index: u64,
+        name: String,
+    },
+}
+
+pub(crate) fn pending_changes(zip_file: *mut zip_t) -> ZipResult<Vec<PendingChange>> {
+    let unchanged = ZIP_FL_UNCHANGED as zip_flags_t;
+    let entries = unsafe { zip_get_num_entries(zip_file, 0) }.max(0) as zip_uint64_t;
+    let mut changes = Vec::new();
+
+    for index in 0..entries {
+        let original = match metadata::stat_index(zip_file, index, unchanged) {
+            Ok(original) => original,
+            Err(_) => {
+                // Entries added and then reverted stay behind as empty slots.
+                if let Ok(current) = metadata::stat_index(zip_file, index, 0) {
+                    let name = current.name.unwrap_or_default();
+                    changes.push(PendingChange::Added { index, name });
+                }
+                continue;
+            }
+        };
+        let original_name = original.name.clone().unwrap_or_default();
+
+        let current = match metadata::stat_index(zip_file, index, 0) {
+            Ok(current) => current,
+            Err(ZipError::Deleted) => {
+                changes.push(PendingChange::Deleted {
+                    index,
+                    name: original_name,
+                });
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let current_name = current.name.clone().unwrap_or_default();
+
+        if current_name != original_name {
+            changes.push(PendingChange::Renamed {
+                index,
+                from: original_name,
+                to: current_name.clone(),
+            });
+        }
+
+        // A new source rarely knows its CRC up front, so a replaced entry shows
+        // up here even when its size happens to match the original.
+        if current.size != original.size || current.crc32 != original.crc32 {
+            changes.push(PendingChange::Replaced {
+                index,
+                name: current_name,
+            });
+        }
+    }
+
+    Ok(changes)
+}
@@ -0,0 +1,74 @@
+use std::slice;
+
+use crate::*;
+
+/// How the bytes of a comment being written are labelled in the archive.
+/// Reading always goes through [`ZipFile::comment`] and friends, which convert
+/// CP437 to UTF-8, or the `raw_*` variants, which return the stored bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommentEncoding {
+    /// Stored as UTF-8 if the bytes are valid UTF-8 and not plain ASCII,
+    /// otherwise as CP437.
+    #[default]
+    Guess,
+    Utf8,
+    Cp437,
+}
+
+impl CommentEncoding {
+    pub fn flags(&self) -> zip_flags_t {
+        let flags = match self {
+            Self::Guess => ZIP_FL_ENC_GUESS,
+            Self::Utf8 => ZIP_FL_ENC_UTF_8,
+            Self::Cp437 => ZIP_FL_ENC_CP437,
+        };
+
+        flags as zip_flags_t
+    }
+}
+
+pub(crate) fn archive_comment(zip_file: *mut zip_t, flags: zip_flags_t) -> ZipResult<Vec<u8>> {
+    let mut len = 0;
+
+    unsafe {
+        let comment = zip_get_archive_comment(zip_file, &mut len, flags);
+        comment_bytes(zip_file, comment, len as usize)
+    }
+}
+
+pub(crate) fn entry_comment(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    flags: zip_flags_t,
+) -> ZipResult<Vec<u8>> {
+    let mut len = 0;
+
+    unsafe {
+        let comment = zip_file_get_comment(zip_file, index, &mut len, flags);
+        comment_bytes(zip_file, comment, len as usize)
+    }
+}
+
+/// Comments are limited to 65535 bytes by the ZIP format.
+pub(crate) fn comment_len(comment: &[u8]) -> ZipResult<zip_uint16_t> {
+    zip_uint16_t::try_from(comment.len()).map_err(|_| ZipError::Invalid)
+}
+
+unsafe fn comment_bytes(
+    zip_file: *mut zip_t,
+    comment: *const std::os::raw::c_char,
+    len: usize,
+) -> ZipResult<Vec<u8>> {
+    if comment.is_null() {
+        return Err(ZipError::from_archive(zip_file));
+    }
+
+    Ok(slice::from_raw_parts(comment as *const u8, len).to_vec())
+}
+
+pub(crate) fn to_text(comment: Vec<u8>) -> String {
+    match String::from_utf8(comment) {
+        Ok(comment) => comment,
+        Err(error) => String::from_utf8_lossy(error.as_bytes()).into_owned(),
+    }
+}
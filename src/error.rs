@@ -0,0 +1,341 @@
+use std::error::Error;
+use std::ffi::NulError;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::raw::c_int;
+use std::path::PathBuf;
+
+use crate::*;
+
+pub type ZipResult<T> = Result<T, ZipError>;
+
+/// Errors reported by libzip, one variant per `ZIP_ER_*` code, plus the few
+/// failures detected on the Rust side before libzip is ever called.
+///
+/// Variants whose libzip error type is `ZIP_ET_SYS` carry the system error
+/// (`errno`) that caused them, exposed through [`Error::source`].
+#[derive(Debug)]
+pub enum ZipError {
+    MultiDisk,
+    Rename(Option<io::Error>),
+    Close(Option<io::Error>),
+    Seek(Option<io::Error>),
+    Read(Option<io::Error>),
+    Write(Option<io::Error>),
+    Crc,
+    ZipClosed,
+    NoEntry,
+    Exists,
+    Open(Option<io::Error>),
+    TmpOpen(Option<io::Error>),
+    Zlib(c_int),
+    Memory,
+    Changed,
+    CompressionNotSupported,
+    Eof,
+    Invalid,
+    NotZip,
+    Internal,
+    Inconsistent(c_int),
+    Remove(Option<io::Error>),
+    Deleted,
+    EncryptionNotSupported,
+    ReadOnly,
+    NoPassword,
+    WrongPassword,
+    OperationNotSupported,
+    InUse,
+    Tell(Option<io::Error>),
+    CompressedData,
+    Cancelled,
+    DataLength,
+    NotAllowed,
+    Unknown(c_int),
+    NotOpen,
+    InvalidString(NulError),
+    InvalidPath(PathBuf),
+    InvalidPattern(glob::PatternError),
+    UnsafeEntryName(String),
+    SymlinkRefused(String),
+    Io(io::Error),
+}
+
+impl ZipError {
+    /// Builds the error for a bare `ZIP_ER_*` code, as returned by `zip_open`.
+    pub fn from_code(code: c_int) -> Self {
+        unsafe {
+            let mut error = MaybeUninit::<zip_error_t>::uninit();
+            zip_error_init_with_code(error.as_mut_ptr(), code);
+            let result = Self::from_zip_error(error.as_ptr());
+            zip_error_fini(error.as_mut_ptr());
+            result
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `error` must point to an initialized `zip_error_t`.
+    pub unsafe fn from_zip_error(error: *const zip_error_t) -> Self {
+        let zip_err = zip_error_code_zip(error);
+        let sys_err = zip_error_code_system(error);
+        let sys_type = zip_error_system_type(error);
+
+        Self::new(zip_err, sys_type, sys_err)
+    }
+
+    /// # Safety
+    ///
+    /// `zip` must be a valid, open archive handle.
+    pub unsafe fn from_archive(zip: *mut zip_t) -> Self {
+        Self::from_zip_error(zip_get_error(zip))
+    }
+
+    /// # Safety
+    ///
+    /// `file` must be a valid, open entry handle.
+    pub unsafe fn from_file(file: *mut zip_file_t) -> Self {
+        Self::from_zip_error(zip_file_get_error(file))
+    }
+
+    fn new(zip_err: c_int, sys_type: c_int, sys_err: c_int) -> Self {
+        // An error raised by a Rust source is passed on as is rather than
+        // rebuilt from its errno, which custom I/O errors do not even have.
+        let system = source::take_io_error(zip_err).or(match sys_type as u32 {
+            ZIP_ET_SYS if sys_err != 0 => Some(io::Error::from_raw_os_error(sys_err)),
+            _ => None,
+        });
+
+        let Ok(code) = u32::try_from(zip_err) else {
+            return Self::Unknown(zip_err);
+        };
+
+        match code {
+            ZIP_ER_MULTIDISK => Self::MultiDisk,
+            ZIP_ER_RENAME => Self::Rename(system),
+            ZIP_ER_CLOSE => Self::Close(system),
+            ZIP_ER_SEEK => Self::Seek(system),
+            ZIP_ER_READ => Self::Read(system),
+            ZIP_ER_WRITE => Self::Write(system),
+            ZIP_ER_CRC => Self::Crc,
+            ZIP_ER_ZIPCLOSED => Self::ZipClosed,
+            ZIP_ER_NOENT => Self::NoEntry,
+            ZIP_ER_EXISTS => Self::Exists,
+            ZIP_ER_OPEN => Self::Open(system),
+            ZIP_ER_TMPOPEN => Self::TmpOpen(system),
+            ZIP_ER_ZLIB => Self::Zlib(sys_err),
+            ZIP_ER_MEMORY => Self::Memory,
+            ZIP_ER_CHANGED => Self::Changed,
+            ZIP_ER_COMPNOTSUPP => Self::CompressionNotSupported,
+            ZIP_ER_EOF => Self::Eof,
+            ZIP_ER_INVAL => Self::Invalid,
+            ZIP_ER_NOZIP => Self::NotZip,
+            ZIP_ER_INTERNAL => Self::Internal,
+            ZIP_ER_INCONS => Self::Inconsistent(sys_err),
+            ZIP_ER_REMOVE => Self::Remove(system),
+            ZIP_ER_DELETED => Self::Deleted,
+            ZIP_ER_ENCRNOTSUPP => Self::EncryptionNotSupported,
+            ZIP_ER_RDONLY => Self::ReadOnly,
+            ZIP_ER_NOPASSWD => Self::NoPassword,
+            ZIP_ER_WRONGPASSWD => Self::WrongPassword,
+            ZIP_ER_OPNOTSUPP => Self::OperationNotSupported,
+            ZIP_ER_INUSE => Self::InUse,
+            ZIP_ER_TELL => Self::Tell(system),
+            ZIP_ER_COMPRESSED_DATA => Self::CompressedData,
+            ZIP_ER_CANCELLED => Self::Cancelled,
+            ZIP_ER_DATA_LENGTH => Self::DataLength,
+            ZIP_ER_NOT_ALLOWED => Self::NotAllowed,
+            _ => Self::Unknown(zip_err),
+        }
+    }
+
+    /// The `ZIP_ER_*` code of this error, or `None` for errors raised on the Rust side.
+    pub fn code(&self) -> Option<c_int> {
+        let code = match self {
+            Self::MultiDisk => ZIP_ER_MULTIDISK,
+            Self::Rename(_) => ZIP_ER_RENAME,
+            Self::Close(_) => ZIP_ER_CLOSE,
+            Self::Seek(_) => ZIP_ER_SEEK,
+            Self::Read(_) => ZIP_ER_READ,
+            Self::Write(_) => ZIP_ER_WRITE,
+            Self::Crc => ZIP_ER_CRC,
+            Self::ZipClosed => ZIP_ER_ZIPCLOSED,
+            Self::NoEntry => ZIP_ER_NOENT,
+            Self::Exists => ZIP_ER_EXISTS,
+            Self::Open(_) => ZIP_ER_OPEN,
+            Self::TmpOpen(_) => ZIP_ER_TMPOPEN,
+            Self::Zlib(_) => ZIP_ER_ZLIB,
+            Self::Memory => ZIP_ER_MEMORY,
+            Self::Changed => ZIP_ER_CHANGED,
+            Self::CompressionNotSupported => ZIP_ER_COMPNOTSUPP,
+            Self::Eof => ZIP_ER_EOF,
+            Self::Invalid => ZIP_ER_INVAL,
+            Self::NotZip => ZIP_ER_NOZIP,
+            Self::Internal => ZIP_ER_INTERNAL,
+            Self::Inconsistent(_) => ZIP_ER_INCONS,
+            Self::Remove(_) => ZIP_ER_REMOVE,
+            Self::Deleted => ZIP_ER_DELETED,
+            Self::EncryptionNotSupported => ZIP_ER_ENCRNOTSUPP,
+            Self::ReadOnly => ZIP_ER_RDONLY,
+            Self::NoPassword => ZIP_ER_NOPASSWD,
+            Self::WrongPassword => ZIP_ER_WRONGPASSWD,
+            Self::OperationNotSupported => ZIP_ER_OPNOTSUPP,
+            Self::InUse => ZIP_ER_INUSE,
+            Self::Tell(_) => ZIP_ER_TELL,
+            Self::CompressedData => ZIP_ER_COMPRESSED_DATA,
+            Self::Cancelled => ZIP_ER_CANCELLED,
+            Self::DataLength => ZIP_ER_DATA_LENGTH,
+            Self::NotAllowed => ZIP_ER_NOT_ALLOWED,
+            Self::Unknown(code) => return Some(*code),
+            Self::NotOpen
+            | Self::InvalidString(_)
+            | Self::InvalidPath(_)
+            | Self::InvalidPattern(_)
+            | Self::UnsafeEntryName(_)
+            | Self::SymlinkRefused(_)
+            | Self::Io(_) => return None,
+        };
+
+        Some(code as c_int)
+    }
+
+    fn system_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::Rename(error)
+            | Self::Close(error)
+            | Self::Seek(error)
+            | Self::Read(error)
+            | Self::Write(error)
+            | Self::Open(error)
+            | Self::TmpOpen(error)
+            | Self::Remove(error)
+            | Self::Tell(error) => error.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ZipError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::MultiDisk => "Multi-disk zip archives not supported",
+            Self::Rename(_) => "Renaming temporary file failed",
+            Self::Close(_) => "Closing zip archive failed",
+            Self::Seek(_) => "Seek error",
+            Self::Read(_) => "Read error",
+            Self::Write(_) => "Write error",
+            Self::Crc => "CRC error",
+            Self::ZipClosed => "Containing zip archive was closed",
+            Self::NoEntry => "No such file",
+            Self::Exists => "File already exists",
+            Self::Open(_) => "Can't open file",
+            Self::TmpOpen(_) => "Failure to create temporary file",
+            Self::Zlib(code) => return write!(f, "Zlib error ({})", code),
+            Self::Memory => "Malloc failure",
+            Self::Changed => "Entry has been changed",
+            Self::CompressionNotSupported => "Compression method not supported",
+            Self::Eof => "Premature end of file",
+            Self::Invalid => "Invalid argument",
+            Self::NotZip => "Not a zip archive",
+            Self::Internal => "Internal error",
+            Self::Inconsistent(_) => "Zip archive inconsistent",
+            Self::Remove(_) => "Can't remove file",
+            Self::Deleted => "Entry has been deleted",
+            Self::EncryptionNotSupported => "Encryption method not supported",
+            Self::ReadOnly => "Read-only archive",
+            Self::NoPassword => "No password provided",
+            Self::WrongPassword => "Wrong password provided",
+            Self::OperationNotSupported => "Operation not supported",
+            Self::InUse => "Resource still in use",
+            Self::Tell(_) => "Tell error",
+            Self::CompressedData => "Compressed data invalid",
+            Self::Cancelled => "Operation cancelled",
+            Self::DataLength => "Unexpected length of data",
+            Self::NotAllowed => "Not allowed in torrentzip",
+            Self::Unknown(code) => return write!(f, "Unknown libzip error ({})", code),
+            Self::NotOpen => "Zip file is not open",
+            Self::InvalidString(_) => "String contains an interior nul byte",
+            Self::InvalidPath(path) => {
+                return write!(f, "Path is not valid UTF-8: {}", path.display())
+            }
+            Self::InvalidPattern(error) => return write!(f, "Invalid glob pattern: {}", error),
+            Self::UnsafeEntryName(name) => {
+                return write!(
+                    f,
+                    "Entry would be extracted outside the destination: {}",
+                    name
+                )
+            }
+            Self::SymlinkRefused(name) => {
+                return write!(f, "Symlink entries are not allowed: {}", name)
+            }
+            Self::Io(error) => return write!(f, "I/O error: {}", error),
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ZipError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidString(error) => Some(error),
+            Self::InvalidPattern(error) => Some(error),
+            Self::Io(error) => Some(error),
+            _ => self.system_error().map(|error| error as _),
+        }
+    }
+}
+
+impl From<NulError> for ZipError {
+    fn from(error: NulError) -> Self {
+        Self::InvalidString(error)
+    }
+}
+
+impl From<glob::PatternError> for ZipError {
+    fn from(error: glob::PatternError) -> Self {
+        Self::InvalidPattern(error)
+    }
+}
+
+impl From<io::Error> for ZipError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<ZipError> for io::Error {
+    fn from(error: ZipError) -> Self {
+        let kind = match &error {
+            ZipError::Io(error) => error.kind(),
+            ZipError::NoEntry => io::ErrorKind::NotFound,
+            ZipError::Exists => io::ErrorKind::AlreadyExists,
+            ZipError::Invalid
+            | ZipError::InvalidString(_)
+            | ZipError::InvalidPath(_)
+            | ZipError::InvalidPattern(_) => io::ErrorKind::InvalidInput,
+            ZipError::UnsafeEntryName(_) => io::ErrorKind::InvalidData,
+            ZipError::SymlinkRefused(_) => io::ErrorKind::PermissionDenied,
+            ZipError::Crc | ZipError::CompressedData | ZipError::NotZip => {
+                io::ErrorKind::InvalidData
+            }
+            ZipError::Eof => io::ErrorKind::UnexpectedEof,
+            ZipError::OperationNotSupported
+            | ZipError::CompressionNotSupported
+            | ZipError::EncryptionNotSupported => io::ErrorKind::Unsupported,
+            ZipError::NoPassword | ZipError::WrongPassword | ZipError::ReadOnly => {
+                io::ErrorKind::PermissionDenied
+            }
+            ZipError::Memory => io::ErrorKind::OutOfMemory,
+            // Not `Interrupted`: `io::copy` and friends retry on that.
+            ZipError::Cancelled => io::ErrorKind::Other,
+            _ => match error.system_error() {
+                Some(system) => system.kind(),
+                None => io::ErrorKind::Other,
+            },
+        };
+
+        io::Error::new(kind, error)
+    }
+}
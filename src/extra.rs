@@ -0,0 +1,389 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::*;
+
+/// Which copy of an entry's extra fields to work on. The local header and the
+/// central directory each carry their own list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExtraFieldLocation {
+    Local,
+    Central,
+    #[default]
+    Both,
+}
+
+impl ExtraFieldLocation {
+    pub fn flags(&self) -> zip_flags_t {
+        let flags = match self {
+            Self::Local => ZIP_FL_LOCAL,
+            Self::Central => ZIP_FL_CENTRAL,
+            Self::Both => ZIP_FL_LOCAL | ZIP_FL_CENTRAL,
+        };
+
+        flags as zip_flags_t
+    }
+
+    pub(crate) fn split(&self) -> &'static [Self] {
+        match self {
+            Self::Local => &[Self::Local],
+            Self::Central => &[Self::Central],
+            Self::Both => &[Self::Local, Self::Central],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraField {
+    pub id: u16,
+    pub data: Vec<u8>,
+}
+
+/// An extra field with a known layout. `encode` receives a single location,
+/// never [`ExtraFieldLocation::Both`], since some fields are shorter in the
+/// central directory.
+pub trait ExtraFieldData: Sized {
+    const ID: u16;
+
+    fn parse(data: &[u8]) -> Option<Self>;
+
+    fn encode(&self, location: ExtraFieldLocation) -> Vec<u8>;
+}
+
+/// Extended timestamp (0x5455), whole seconds since the Unix epoch stored in
+/// 32 bits, so times outside 1901-12-13..=2038-01-19 are saturated. The
+/// central directory copy only ever holds `modified`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExtendedTimestamp {
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+}
+
+impl ExtraFieldData for ExtendedTimestamp {
+    const ID: u16 = 0x5455;
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        let (&flags, mut times) = data.split_first()?;
+        let mut next = |bit: u8| {
+            if flags & bit == 0 || times.len() < 4 {
+                return None;
+            }
+            let (time, rest) = times.split_at(4);
+            times = rest;
+            let time = i32::from_le_bytes(time.try_into().ok()?);
+            Some(metadata::system_time(time as time_t))
+        };
+
+        Some(Self {
+            modified: next(0x01),
+            accessed: next(0x02),
+            created: next(0x04),
+        })
+    }
+
+    fn encode(&self, location: ExtraFieldLocation) -> Vec<u8> {
+        let times = [self.modified, self.accessed, self.created];
+        let flags = times
+            .iter()
+            .enumerate()
+            .filter(|(_, time)| time.is_some())
+            .fold(0u8, |flags, (bit, _)| flags | 1 << bit);
+
+        let mut data = vec![flags];
+        let written = match location {
+            ExtraFieldLocation::Central => &times[..1],
+            _ => &times[..],
+        };
+        for time in written.iter().flatten() {
+            let secs = metadata::unix_seconds(*time);
+            let secs = i32::try_from(secs).unwrap_or(if secs < 0 { i32::MIN } else { i32::MAX });
+            data.extend_from_slice(&secs.to_le_bytes());
+        }
+
+        data
+    }
+}
+
+/// Info-ZIP Unix owner (0x7875).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnixOwner {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl ExtraFieldData for UnixOwner {
+    const ID: u16 = 0x7875;
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        let (&version, mut data) = data.split_first()?;
+        if version != 1 {
+            return None;
+        }
+
+        let mut next = || {
+            let (&size, rest) = data.split_first()?;
+            let size = size as usize;
+            if size > 8 || rest.len() < size {
+                return None;
+            }
+            let (value, rest) = rest.split_at(size);
+            data = rest;
+
+            let mut bytes = [0u8; 8];
+            bytes[..size].copy_from_slice(value);
+            u32::try_from(u64::from_le_bytes(bytes)).ok()
+        };
+
+        Some(Self {
+            uid: next()?,
+            gid: next()?,
+        })
+    }
+
+    fn encode(&self, _location: ExtraFieldLocation) -> Vec<u8> {
+        let mut data = vec![1, 4];
+        data.extend_from_slice(&self.uid.to_le_bytes());
+        data.push(4);
+        data.extend_from_slice(&self.gid.to_le_bytes());
+        data
+    }
+}
+
+/// NTFS times (0x000a), kept at their full 100 ns resolution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NtfsTimes {
+    pub modified: SystemTime,
+    pub accessed: SystemTime,
+    pub created: SystemTime,
+}
+
+const NTFS_TIMES_TAG: u16 = 0x0001;
+const FILETIME_UNIX_EPOCH: Duration = Duration::from_secs(11_644_473_600);
+
+impl ExtraFieldData for NtfsTimes {
+    const ID: u16 = 0x000a;
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        // Four reserved bytes, then tagged attributes.
+        let mut attributes = data.get(4..)?;
+        while attributes.len() >= 4 {
+            let tag = u16::from_le_bytes([attributes[0], attributes[1]]);
+            let size = u16::from_le_bytes([attributes[2], attributes[3]]) as usize;
+            let value = attributes.get(4..4 + size)?;
+
+            if tag == NTFS_TIMES_TAG && size >= 24 {
+                let time = |at: usize| {
+                    let ticks = u64::from_le_bytes(value[at..at + 8].try_into().ok()?);
+                    from_filetime(ticks)
+                };
+
+                return Some(Self {
+                    modified: time(0)?,
+                    accessed: time(8)?,
+                    created: time(16)?,
+                });
+            }
+            attributes = &attributes[4 + size..];
+        }
+
+        None
+    }
+
+    fn encode(&self, _location: ExtraFieldLocation) -> Vec<u8> {
+        let mut data = vec![0; 4];
+        data.extend_from_slice(&NTFS_TIMES_TAG.to_le_bytes());
+        data.extend_from_slice(&24u16.to_le_bytes());
+        for time in [self.modified, self.accessed, self.created] {
+            data.extend_from_slice(&to_filetime(time).to_le_bytes());
+        }
+        data
+    }
+}
+
+fn from_filetime(ticks: u64) -> Option<SystemTime> {
+    let since_1601 = Duration::from_nanos(ticks.checked_mul(100)?);
+
+    match since_1601.checked_sub(FILETIME_UNIX_EPOCH) {
+        Some(after) => UNIX_EPOCH.checked_add(after),
+        None => UNIX_EPOCH.checked_sub(FILETIME_UNIX_EPOCH - since_1601),
+    }
+}
+
+fn to_filetime(time: SystemTime) -> u64 {
+    let since_1601 = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => FILETIME_UNIX_EPOCH + after,
+        Err(error) => FILETIME_UNIX_EPOCH.saturating_sub(error.duration()),
+    };
+
+    (since_1601.as_nanos() / 100) as u64
+}
+
+/// Info-ZIP Unicode path (0x7075): the UTF-8 name along with the CRC-32 of the
+/// header name it stands in for.
+///
+/// libzip applies this field itself when reading, so entry names already
+/// reflect it, and it refuses to write it; the type is meant for tools that
+/// handle raw extra field data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnicodePath {
+    pub name_crc32: u32,
+    pub name: String,
+}
+
+impl UnicodePath {
+    pub fn new(header_name: &[u8], name: &str) -> Self {
+        Self {
+            name_crc32: crc32(header_name),
+            name: name.to_string(),
+        }
+    }
+
+    /// Whether the field still belongs to `header_name`, i.e. the name was not
+    /// changed by a tool unaware of this field.
+    pub fn matches(&self, header_name: &[u8]) -> bool {
+        self.name_crc32 == crc32(header_name)
+    }
+}
+
+impl ExtraFieldData for UnicodePath {
+    const ID: u16 = 0x7075;
+
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 5 || data[0] != 1 {
+            return None;
+        }
+
+        Some(Self {
+            name_crc32: u32::from_le_bytes(data[1..5].try_into().ok()?),
+            name: String::from_utf8(data[5..].to_vec()).ok()?,
+        })
+    }
+
+    fn encode(&self, _location: ExtraFieldLocation) -> Vec<u8> {
+        let mut data = vec![1];
+        data.extend_from_slice(&self.name_crc32.to_le_bytes());
+        data.extend_from_slice(self.name.as_bytes());
+        data
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+pub(crate) fn extra_fields(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    location: ExtraFieldLocation,
+) -> ZipResult<Vec<ExtraField>> {
+    let flags = location.flags();
+
+    unsafe {
+        let count = zip_file_extra_fields_count(zip_file, index, flags);
+        if count < 0 {
+            return Err(ZipError::from_archive(zip_file));
+        }
+
+        (0..count as zip_uint16_t)
+            .map(|field| {
+                let mut id = 0;
+                let mut len = 0;
+                let data =
+                    zip_file_extra_field_get(zip_file, index, field, &mut id, &mut len, flags);
+                if data.is_null() {
+                    return Err(ZipError::from_archive(zip_file));
+                }
+
+                Ok(ExtraField {
+                    id,
+                    data: field_data(data, len),
+                })
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn extra_field(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    id: u16,
+    location: ExtraFieldLocation,
+) -> ZipResult<Option<Vec<u8>>> {
+    let mut len = 0;
+
+    unsafe {
+        let data =
+            zip_file_extra_field_get_by_id(zip_file, index, id, 0, &mut len, location.flags());
+        if data.is_null() {
+            return match ZipError::from_archive(zip_file) {
+                ZipError::NoEntry => Ok(None),
+                error => Err(error),
+            };
+        }
+
+        Ok(Some(field_data(data, len)))
+    }
+}
+
+/// Replaces the first field with `id` at each location, or appends one.
+pub(crate) fn set_extra_field(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    id: u16,
+    data: &[u8],
+    location: ExtraFieldLocation,
+) -> ZipResult<()> {
+    let len = zip_uint16_t::try_from(data.len()).map_err(|_| ZipError::Invalid)?;
+
+    for &location in location.split() {
+        let flags = location.flags();
+
+        unsafe {
+            let existing = zip_file_extra_fields_count_by_id(zip_file, index, id, flags);
+            if existing < 0 {
+                return Err(ZipError::from_archive(zip_file));
+            }
+            let field = if existing > 0 {
+                0
+            } else {
+                ZIP_EXTRA_FIELD_NEW as zip_uint16_t
+            };
+
+            if zip_file_extra_field_set(zip_file, index, id, field, data.as_ptr(), len, flags) != 0
+            {
+                return Err(ZipError::from_archive(zip_file));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn delete_extra_field(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    id: u16,
+    location: ExtraFieldLocation,
+) -> ZipResult<()> {
+    let all = ZIP_EXTRA_FIELD_ALL as zip_uint16_t;
+
+    unsafe {
+        if zip_file_extra_field_delete_by_id(zip_file, index, id, all, location.flags()) != 0 {
+            return Err(ZipError::from_archive(zip_file));
+        }
+    }
+
+    Ok(())
+}
+
+unsafe fn field_data(data: *const zip_uint8_t, len: zip_uint16_t) -> Vec<u8> {
+    if len == 0 {
+        return Vec::new();
+    }
+
+    std::slice::from_raw_parts(data, len as usize).to_vec()
+}
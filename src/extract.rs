@@ -0,0 +1,345 @@
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions as FsOpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtractOptions {
+    overwrite: bool,
+    allow_symlinks: bool,
+    preserve_permissions: bool,
+    preserve_mtime: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: true,
+            allow_symlinks: true,
+            preserve_permissions: true,
+            preserve_mtime: true,
+        }
+    }
+}
+
+impl ExtractOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces files that already exist in the destination. When disabled,
+    /// extraction stops with an I/O `AlreadyExists` error instead.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Recreates symlink entries. When disabled, any symlink entry aborts the
+    /// extraction with [`ZipError::SymlinkRefused`]. Links are created after
+    /// all other entries, and their targets must stay inside the destination
+    /// without passing through another link.
+    pub fn allow_symlinks(mut self, allow_symlinks: bool) -> Self {
+        self.allow_symlinks = allow_symlinks;
+        self
+    }
+
+    /// Applies the Unix permission bits (without setuid, setgid and sticky)
+    /// stored in the external attributes of entries created on Unix.
+    pub fn preserve_permissions(mut self, preserve_permissions: bool) -> Self {
+        self.preserve_permissions = preserve_permissions;
+        self
+    }
+
+    pub fn preserve_mtime(mut self, preserve_mtime: bool) -> Self {
+        self.preserve_mtime = preserve_mtime;
+        self
+    }
+}
+
+enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+struct Attributes {
+    kind: EntryKind,
+    unix_mode: Option<u32>,
+}
+
+pub(crate) fn extract_all(zip: &ZipFile, dest: &Path, options: &ExtractOptions) -> ZipResult<()> {
+    fs::create_dir_all(dest)?;
+    let mut directories = Vec::new();
+    let mut symlinks = Vec::new();
+
+    for info in zip.iter() {
+        let info = info?;
+        let name = info.name.to_string_lossy();
+        let Some(relative) = sanitize_name(&name)? else {
+            continue;
+        };
+
+        let path = dest.join(&relative);
+        let attributes = attributes(&info.metadata);
+        let modified = info.metadata.modified;
+
+        match attributes.kind {
+            EntryKind::Directory => {
+                check_no_symlinks(dest, &relative, &name)?;
+                fs::create_dir_all(&path)?;
+                directories.push((path, attributes.unix_mode, modified));
+                continue;
+            }
+            EntryKind::Symlink => {
+                if !options.allow_symlinks {
+                    return Err(ZipError::SymlinkRefused(name));
+                }
+                symlinks.push((info.index, name, relative));
+                continue;
+            }
+            EntryKind::File => {
+                check_no_symlinks(dest, parent(&relative), &name)?;
+                create_parent(&path)?;
+                let modified = modified.filter(|_| options.preserve_mtime);
+                extract_file(zip, info.index, &path, modified, options)?;
+            }
+        }
+
+        if options.preserve_permissions {
+            set_permissions(&path, attributes.unix_mode)?;
+        }
+    }
+
+    // Links are only created once every file is written, so no entry can be
+    // extracted through a link that an earlier entry planted.
+    let links: HashSet<&Path> = symlinks
+        .iter()
+        .map(|(_, _, relative)| relative.as_path())
+        .collect();
+    for (index, name, relative) in &symlinks {
+        check_no_symlinks(dest, parent(relative), name)?;
+        create_parent(&dest.join(relative))?;
+        extract_symlink(zip, *index, name, dest, relative, &links, options)?;
+    }
+
+    // Directories are finished last: creating their children would otherwise
+    // bump the restored mtime, and a read-only mode could block extraction.
+    for (path, unix_mode, modified) in directories.into_iter().rev() {
+        if options.preserve_mtime {
+            if let Some(modified) = modified {
+                set_directory_mtime(&path, modified);
+            }
+        }
+        if options.preserve_permissions {
+            set_permissions(&path, unix_mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Turns an entry name into a path relative to the extraction root, rejecting
+/// anything that could escape it (zip-slip): `..` components, absolute paths,
+/// drive letters and other prefixes. Both `/` and `\` count as separators.
+/// Returns `None` for names that resolve to the root itself, such as `./`.
+pub(crate) fn sanitize_name(name: &str) -> ZipResult<Option<PathBuf>> {
+    let unsafe_name = || ZipError::UnsafeEntryName(name.to_string());
+
+    if name.starts_with(['/', '\\']) || name.contains('\0') {
+        return Err(unsafe_name());
+    }
+
+    let mut path = PathBuf::new();
+    for component in name.split(['/', '\\']) {
+        match component {
+            "" | "." => continue,
+            ".." => return Err(unsafe_name()),
+            _ if component.contains(':') => return Err(unsafe_name()),
+            _ => {}
+        }
+
+        // Platform specific prefixes (`C:`, `\\?\`) are already rejected above,
+        // this catches anything else the platform does not consider normal.
+        let mut components = Path::new(component).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => path.push(component),
+            _ => return Err(unsafe_name()),
+        }
+    }
+
+    Ok((!path.as_os_str().is_empty()).then_some(path))
+}
+
+fn attributes(metadata: &EntryMetadata) -> Attributes {
+    let kind = if metadata.is_symlink() {
+        EntryKind::Symlink
+    } else if metadata.is_dir() {
+        EntryKind::Directory
+    } else {
+        EntryKind::File
+    };
+
+    Attributes {
+        kind,
+        unix_mode: metadata.unix_mode(),
+    }
+}
+
+fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn create_parent(path: &Path) -> ZipResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(())
+}
+
+/// Fails if `relative`, or any directory on the way to it, already exists as
+/// a symlink below `dest`, so nothing is ever written through a link.
+fn check_no_symlinks(dest: &Path, relative: &Path, name: &str) -> ZipResult<()> {
+    let mut path = dest.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                return Err(ZipError::UnsafeEntryName(name.to_string()));
+            }
+            Ok(_) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => break,
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_file(
+    zip: &ZipFile,
+    index: u64,
+    path: &Path,
+    modified: Option<SystemTime>,
+    options: &ExtractOptions,
+) -> ZipResult<()> {
+    let mut file_options = FsOpenOptions::new();
+    file_options.write(true);
+    if options.overwrite {
+        file_options.create(true).truncate(true);
+    } else {
+        file_options.create_new(true);
+    }
+
+    // A link left at the destination is replaced rather than written through.
+    if options.overwrite
+        && path
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+    {
+        fs::remove_file(path)?;
+    }
+
+    let mut entry = zip.entry_by_index(index, true)?;
+    let mut file = file_options.open(path)?;
+    io::copy(&mut entry, &mut file)?;
+    if let Some(modified) = modified {
+        file.set_modified(modified)?;
+    }
+
+    Ok(())
+}
+
+fn extract_symlink(
+    zip: &ZipFile,
+    index: u64,
+    name: &str,
+    dest: &Path,
+    relative: &Path,
+    links: &HashSet<&Path>,
+    options: &ExtractOptions,
+) -> ZipResult<()> {
+    let unsafe_name = || ZipError::UnsafeEntryName(name.to_string());
+
+    let mut target = Vec::new();
+    io::copy(&mut zip.entry_by_index(index, true)?, &mut target)?;
+    let target = String::from_utf8_lossy(&target).into_owned();
+    if target.starts_with(['/', '\\']) || target.contains(':') || target.contains('\0') {
+        return Err(unsafe_name());
+    }
+
+    // The target is resolved against the link's own directory and has to end
+    // up inside the extraction root. Every directory it passes through must be
+    // a real one: `b -> .` followed by `a -> b/..` would otherwise escape.
+    let mut resolved = parent(relative).to_path_buf();
+    let mut descended = false;
+    for component in target.split(['/', '\\']) {
+        if component.is_empty() || component == "." {
+            continue;
+        }
+        if descended && is_symlink(dest, &resolved, links) {
+            return Err(unsafe_name());
+        }
+        if component == ".." {
+            if !resolved.pop() {
+                return Err(unsafe_name());
+            }
+        } else {
+            resolved.push(component);
+        }
+        descended = true;
+    }
+
+    let path = dest.join(relative);
+    if options.overwrite && path.symlink_metadata().is_ok() {
+        fs::remove_file(&path)?;
+    }
+
+    create_symlink(&target, &path)
+}
+
+/// Whether `relative` is a link, either one this archive creates or one that
+/// already exists in the destination.
+fn is_symlink(dest: &Path, relative: &Path, links: &HashSet<&Path>) -> bool {
+    links.contains(relative)
+        || fs::symlink_metadata(dest.join(relative))
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, path: &Path) -> ZipResult<()> {
+    Ok(std::os::unix::fs::symlink(target, path)?)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &str, _path: &Path) -> ZipResult<()> {
+    Err(ZipError::OperationNotSupported)
+}
+
+#[cfg(unix)]
+fn set_permissions(path: &Path, unix_mode: Option<u32>) -> ZipResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(mode) = unix_mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_permissions(_path: &Path, _unix_mode: Option<u32>) -> ZipResult<()> {
+    Ok(())
+}
+
+/// Directory times are best effort: not every platform lets a directory be
+/// opened to update them.
+fn set_directory_mtime(path: &Path, modified: SystemTime) {
+    if let Ok(directory) = File::open(path) {
+        let _ = directory.set_modified(modified);
+    }
+}
@@ -0,0 +1,195 @@
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use glob::Pattern;
+
+use crate::lock::ArchiveLock;
+use crate::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EntryName {
+    Utf8(String),
+    /// The stored name is not valid UTF-8; these are the bytes as found in the archive.
+    Raw(Vec<u8>),
+}
+
+impl EntryName {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Utf8(name) => name.as_bytes(),
+            Self::Raw(name) => name,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Utf8(name) => Some(name),
+            Self::Raw(_) => None,
+        }
+    }
+
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(self.as_bytes()).into_owned()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryInfo {
+    pub index: u64,
+    pub name: EntryName,
+    pub metadata: EntryMetadata,
+}
+
+/// Iterator over the entries of a [`ZipFile`], created by [`ZipFile::iter`].
+/// Each entry is only looked up when the iterator reaches it. Entries deleted
+/// since the archive was opened are skipped.
+pub struct Entries<'a> {
+    zip_file: Option<*mut zip_t>,
+    lock: Arc<ArchiveLock>,
+    front: u64,
+    back: u64,
+    _zip: PhantomData<&'a ZipFile>,
+}
+
+impl<'a> Entries<'a> {
+    pub(crate) fn new(zip_file: Option<*mut zip_t>, lock: Arc<ArchiveLock>) -> Self {
+        let back = match zip_file {
+            Some(zip_file) => {
+                let _guard = lock.lock();
+                unsafe { zip_get_num_entries(zip_file, 0).max(0) as u64 }
+            }
+            None => 0,
+        };
+
+        Self {
+            zip_file,
+            lock,
+            front: 0,
+            back,
+            _zip: PhantomData,
+        }
+    }
+
+    /// Only yields the entries whose name matches the glob `pattern`. Names are
+    /// matched before the entry is stat'ed, so skipped entries cost a name lookup.
+    pub fn filter_by_glob(self, pattern: &str) -> ZipResult<GlobEntries<'a>> {
+        Ok(GlobEntries {
+            entries: self,
+            pattern: Pattern::new(pattern)?,
+        })
+    }
+
+    fn name(&self, index: u64) -> ZipResult<EntryName> {
+        let _guard = self.lock.lock();
+        let zip_file = self.zip_file.ok_or(ZipError::NotOpen)?;
+        entry_name(zip_file, index)
+    }
+
+    /// The entry at `index`, `None` when it was deleted.
+    fn entry(&self, index: u64) -> Option<ZipResult<EntryInfo>> {
+        match self.name(index) {
+            Ok(name) => Some(self.info(index, name)),
+            Err(ZipError::Deleted) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    fn info(&self, index: u64, name: EntryName) -> ZipResult<EntryInfo> {
+        let _guard = self.lock.lock();
+        let zip_file = self.zip_file.ok_or(ZipError::NotOpen)?;
+
+        Ok(EntryInfo {
+            index,
+            name,
+            metadata: metadata::stat_index(zip_file, index, 0)?,
+        })
+    }
+}
+
+impl Iterator for Entries<'_> {
+    type Item = ZipResult<EntryInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            let index = self.front;
+            self.front += 1;
+
+            if let Some(entry) = self.entry(index) {
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some((self.back - self.front) as usize))
+    }
+}
+
+impl DoubleEndedIterator for Entries<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            self.back -= 1;
+
+            if let Some(entry) = self.entry(self.back) {
+                return Some(entry);
+            }
+        }
+
+        None
+    }
+}
+
+pub struct GlobEntries<'a> {
+    entries: Entries<'a>,
+    pattern: Pattern,
+}
+
+impl Iterator for GlobEntries<'_> {
+    type Item = ZipResult<EntryInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.entries.front < self.entries.back {
+            let index = self.entries.front;
+            self.entries.front += 1;
+
+            let name = match self.entries.name(index) {
+                Ok(name) => name,
+                Err(ZipError::Deleted) => continue,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if self.pattern.matches(&name.to_string_lossy()) {
+                return Some(self.entries.info(index, name));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.entries.size_hint().1)
+    }
+}
+
+pub(crate) fn entry_name(zip_file: *mut zip_t, index: u64) -> ZipResult<EntryName> {
+    unsafe {
+        let name = zip_get_name(zip_file, index, ZIP_FL_ENC_GUESS);
+        if name.is_null() {
+            return Err(ZipError::from_archive(zip_file));
+        }
+
+        if let Ok(name) = CStr::from_ptr(name).to_str() {
+            return Ok(EntryName::Utf8(name.to_string()));
+        }
+
+        let name = zip_get_name(zip_file, index, ZIP_FL_ENC_RAW);
+        if name.is_null() {
+            return Err(ZipError::from_archive(zip_file));
+        }
+
+        Ok(EntryName::Raw(CStr::from_ptr(name).to_bytes().to_vec()))
+    }
+}
@@ -5,20 +5,60 @@
 // include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 include!("zip.rs");
 
-pub type ZipResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+#[cfg(feature = "tokio")]
+mod async_zip;
+mod changes;
+mod comment;
+mod error;
+mod extra;
+mod extract;
+mod iter;
+mod lock;
+mod metadata;
+mod options;
+mod progress;
+mod source;
+mod walk;
+
+#[cfg(feature = "tokio")]
+pub use async_zip::AsyncZipFile;
+pub use changes::PendingChange;
+pub use comment::CommentEncoding;
+pub use error::{ZipError, ZipResult};
+pub use extra::{
+    ExtendedTimestamp, ExtraField, ExtraFieldData, ExtraFieldLocation, NtfsTimes, UnicodePath,
+    UnixOwner,
+};
+pub use extract::ExtractOptions;
+pub use iter::{Entries, EntryInfo, EntryName, GlobEntries};
+pub use metadata::{CompressionMethod, EntryMetadata, HostOs};
+pub use options::{Compression, DropPolicy, EncryptionMethod, EntryOptions, OpenOptions};
+pub use source::{RustSource, RustWritableSource, SetLen};
+pub use walk::{AddDirOptions, SymlinkPolicy};
+
+use lock::{ArchiveGuard, ArchiveLock};
+use options::Password;
+use source::ReaderSource;
 
-use std::error::Error;
 use std::ffi::{c_void, CStr, CString};
 use std::fmt::{Display, Formatter};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::os::raw::c_int;
 use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 #[cfg_attr(feature = "faux", faux::create)]
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct ZipFile {
     file: Option<*mut zip_t>,
     filename: PathBuf,
+    source: Option<*mut zip_source_t>,
+    buffer: Option<Vec<u8>>,
+    drop_policy: DropPolicy,
+    lock: Arc<ArchiveLock>,
 }
 
 #[cfg_attr(feature = "faux", faux::create)]
@@ -27,6 +67,16 @@ pub struct ZipEntry {
     file: Option<*mut zip_file_t>,
     name: String,
     is_open: bool,
+    index: Option<zip_uint64_t>,
+    seek_emulation: bool,
+    password: Option<Password>,
+    lock: Option<Arc<ArchiveLock>>,
+    #[cfg(feature = "tokio")]
+    pending: Option<PendingRead>,
+    #[cfg(feature = "tokio")]
+    read_buf: Vec<u8>,
+    #[cfg(feature = "tokio")]
+    read_pos: usize,
 }
 
 // pub trait ZipEntry: std::io::Read {
@@ -42,58 +92,156 @@ pub trait ZipPack {
 #[cfg_attr(feature = "faux", faux::methods)]
 impl ZipFile {
     pub fn add_buffer(&self, data: &[u8], filename: &str) -> ZipResult<()> {
-        let c_filename = CString::new(filename).unwrap();
-        match self.file {
-            Some(zip_file) => unsafe {
-                let zip_source_err = null_mut();
-                let zip_source = zip_source_buffer_create(
-                    data.as_ptr() as _,
-                    data.len() as zip_uint64_t,
-                    0,
-                    zip_source_err,
-                );
-                let zip_result = zip_file_add(
-                    zip_file,
-                    c_filename.as_ptr(),
-                    zip_source,
-                    ZIP_FL_OVERWRITE | ZIP_FL_ENC_UTF_8,
-                );
-                match zip_result {
-                    -1 => Err("Unable to add buffer to the zip".into()),
-                    _ => Ok(()),
-                }
-            },
-            None => Err("Zip file is not open".into()),
+        self.add_bytes(data.to_vec(), filename, &EntryOptions::default())
+    }
+
+    /// libzip only reads entry data when the archive is closed, so the archive
+    /// takes ownership of `data` and drops it once the entry has been written.
+    pub fn add_bytes(
+        &self,
+        data: impl AsRef<[u8]> + Send + 'static,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        options.validate()?;
+        let zip_source = RustSource::new(Cursor::new(data)).into_raw()?;
+
+        self.add_zip_source(zip_source, filename, options)
+    }
+
+    /// Adds an explicit directory entry (`zip_dir_add`) and returns its index.
+    /// A trailing `/` is appended to `name` by libzip when missing.
+    pub fn add_dir(&self, name: &str) -> ZipResult<u64> {
+        let (_guard, zip_file) = self.archive()?;
+        let c_name = CString::new(name)?;
+
+        let index = unsafe { zip_dir_add(zip_file, c_name.as_ptr(), ZIP_FL_ENC_UTF_8) };
+        if index < 0 {
+            return Err(unsafe { ZipError::from_archive(zip_file) });
         }
+
+        Ok(index as u64)
+    }
+
+    pub fn add_symlink(&self, name: &str, target: &str) -> ZipResult<()> {
+        let options = EntryOptions::new().unix_mode(metadata::S_IFLNK | 0o777);
+
+        self.add_bytes(target.as_bytes().to_vec(), name, &options)
     }
 
     pub fn add_file(&self, src: &Path, filename: &str) -> ZipResult<()> {
-        let c_src = CString::new(src.to_str().unwrap()).unwrap();
-        let c_filename = CString::new(filename).unwrap();
+        self.add_file_with_options(src, filename, &EntryOptions::default())
+    }
 
-        match self.file {
-            Some(zip_file) => {
-                unsafe {
-                    let zip_source_err = null_mut();
-                    let zip_source = zip_source_file_create(c_src.as_ptr(), 0, -1, zip_source_err);
-                    let zip_result = zip_file_add(
-                        zip_file,
-                        c_filename.as_ptr(),
-                        zip_source,
-                        ZIP_FL_OVERWRITE | ZIP_FL_ENC_UTF_8,
-                    );
-
-                    if zip_result == -1 {
-                        return Err("Unable to add file to zip".into());
-                    }
+    pub fn add_file_with_options(
+        &self,
+        src: &Path,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        let c_src = path_to_cstring(src)?;
+        let (_guard, zip_file) = self.archive()?;
+        options.validate()?;
+        let zip_source = unsafe { zip_source_file(zip_file, c_src.as_ptr(), 0, -1) };
+
+        self.add_zip_source(zip_source, filename, options)
+    }
+
+    /// `reader` is only read once the archive is closed, and only once: if that
+    /// close fails or is cancelled, retrying it fails with
+    /// [`ZipError::OperationNotSupported`] and the archive has to be discarded.
+    pub fn add_reader(
+        &self,
+        reader: impl Read + Send + 'static,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        self.file.ok_or(ZipError::NotOpen)?;
+        options.validate()?;
+        let zip_source = source::into_raw(ReaderSource::new(reader))?;
+
+        self.add_zip_source(zip_source, filename, options)
+    }
+
+    fn add_zip_source(
+        &self,
+        zip_source: *mut zip_source_t,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        let _guard = self.lock.lock();
+        let zip_file = match self.file {
+            Some(zip_file) => zip_file,
+            None => {
+                unsafe { zip_source_free(zip_source) };
+                return Err(ZipError::NotOpen);
+            }
+        };
+
+        let existed = match self.index_of(filename, 0) {
+            Ok(index) => index.is_some(),
+            Err(error) => {
+                unsafe { zip_source_free(zip_source) };
+                return Err(error);
+            }
+        };
+
+        let index = unsafe {
+            if zip_source.is_null() {
+                return Err(ZipError::from_archive(zip_file));
+            }
+
+            let c_filename = match CString::new(filename) {
+                Ok(c_filename) => c_filename,
+                Err(error) => {
+                    zip_source_free(zip_source);
+                    return Err(error.into());
                 }
-                Ok(())
+            };
+
+            let zip_result = zip_file_add(
+                zip_file,
+                c_filename.as_ptr(),
+                zip_source,
+                ZIP_FL_OVERWRITE | ZIP_FL_ENC_UTF_8,
+            );
+
+            if zip_result == -1 {
+                zip_source_free(zip_source);
+                return Err(ZipError::from_archive(zip_file));
+            }
+
+            zip_result as zip_uint64_t
+        };
+
+        if let Err(error) = options.apply(zip_file, index) {
+            // A brand new entry is deleted again. One that overwrote an existing
+            // entry is left alone: `zip_unchange` would also undo the changes made
+            // to it earlier, and deleting it would lose the entry altogether.
+            if !existed {
+                let result = unsafe { zip_delete(zip_file, index) };
+                self.get_error(result as i64)?;
             }
-            None => Err("Zip file is not open".into()),
+
+            return Err(error);
+        }
+
+        Ok(())
+    }
+
+    pub fn bytes(&self) -> ZipResult<Vec<u8>> {
+        if self.file.is_some() {
+            return Err(ZipError::InUse);
+        }
+
+        match self.source {
+            Some(source) => read_source(source),
+            None => Err(ZipError::OperationNotSupported),
         }
     }
 
     pub fn close(&mut self) -> ZipResult<()> {
+        let _guard = self.lock.lock();
         match self.file {
             Some(zip_file) => unsafe {
                 let result = zip_close(zip_file);
@@ -101,22 +249,38 @@ impl ZipFile {
                 match result {
                     0 => {
                         self.file = None;
+                        self.lock.release();
                         Ok(())
                     }
-                    _ => {
-                        let msg = zip_strerror(zip_file);
-                        let msg = CStr::from_ptr(msg).to_str()?;
-                        Err(msg.into())
-                    }
+                    _ => Err(ZipError::from_archive(zip_file)),
                 }
             },
             None => Ok(()),
         }
     }
 
+    /// The archive comment, converted to UTF-8 when stored as CP437. Empty when
+    /// the archive has none.
+    pub fn comment(&self) -> ZipResult<String> {
+        let (_guard, zip_file) = self.archive()?;
+
+        comment::archive_comment(zip_file, ZIP_FL_ENC_GUESS as zip_flags_t).map(comment::to_text)
+    }
+
+    /// Closes the archive with `zip_discard`, dropping every pending change.
+    /// Nothing is written to disk.
+    pub fn discard(&mut self) {
+        let _guard = self.lock.lock();
+        if let Some(zip_file) = self.file.take() {
+            unsafe { zip_discard(zip_file) };
+            self.lock.release();
+        }
+    }
+
     pub fn delete_file(&self, filename: &str) -> ZipResult<()> {
+        let _guard = self.lock.lock();
         let file = match self.file {
-            None => return Err("No zip is open".into()),
+            None => return Err(ZipError::NotOpen),
             Some(file) => file,
         };
 
@@ -129,59 +293,148 @@ impl ZipFile {
     }
 
     pub fn entries(&self) -> ZipResult<Vec<ZipEntry>> {
+        let _guard = self.lock.lock();
         if let Some(zip_file) = self.file {
             let num_entries = unsafe { zip_get_num_entries(zip_file, 0) };
 
             let mut entries = Vec::new();
             if let Ok(num_entries) = zip_uint64_t::try_from(num_entries) {
                 for index in 0..num_entries {
-                    let name = unsafe {
-                        let name = zip_get_name(zip_file, index, ZIP_FL_ENC_GUESS);
-                        CStr::from_ptr(name).to_str()
+                    let name = match iter::entry_name(zip_file, index) {
+                        Ok(name) => name,
+                        Err(ZipError::Deleted) => continue,
+                        Err(error) => return Err(error),
                     };
 
-                    if let Ok(name) = name {
-                        let entry = ZipEntry::new(None, name, false);
-                        entries.push(entry);
-                    }
+                    // Entries are looked up by index, so a name that is not UTF-8
+                    // only loses its exact spelling in `ZipEntry::name`.
+                    let name = name.to_string_lossy();
+                    let entry = ZipEntry::in_archive(Some(index), None, &name, self.lock.clone());
+                    entries.push(entry);
                 }
 
                 Ok(entries)
             } else {
-                Err("Invalid number of entries".into())
+                Err(unsafe { ZipError::from_archive(zip_file) })
             }
         } else {
             Ok(Vec::new())
         }
     }
 
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, ZipError> {
+        Self::open_buffer(data, 0)
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, ZipError> {
+        Self::from_bytes(data.to_vec())
+    }
+
+    /// Removes every extra field with `id` from entry `name`.
+    pub fn delete_extra_field(
+        &self,
+        name: &str,
+        id: u16,
+        location: ExtraFieldLocation,
+    ) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        extra::delete_extra_field(zip_file, index, id, location)
+    }
+
+    /// The data of the first extra field with `id` on entry `name`. Fields
+    /// libzip manages itself (Zip64, WinZip AES, Unicode path and comment) are
+    /// never returned.
+    pub fn extra_field(
+        &self,
+        name: &str,
+        id: u16,
+        location: ExtraFieldLocation,
+    ) -> ZipResult<Option<Vec<u8>>> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        extra::extra_field(zip_file, index, id, location)
+    }
+
+    pub fn extra_fields(
+        &self,
+        name: &str,
+        location: ExtraFieldLocation,
+    ) -> ZipResult<Vec<ExtraField>> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        extra::extra_fields(zip_file, index, location)
+    }
+
+    pub fn extract_to(&self, entry_name: &str, dest: &Path) -> ZipResult<u64> {
+        let index = self.index_of(entry_name, 0)?.ok_or(ZipError::NoEntry)?;
+        let mut entry = self.entry_by_index(index, true)?;
+        let mut file = std::fs::File::create(dest)?;
+
+        Ok(std::io::copy(&mut entry, &mut file)?)
+    }
+
     pub fn filename(&self) -> &Path {
         self.filename.as_path()
     }
 
-    pub fn get_entry(&self, entry_name: &str, open: bool) -> Option<ZipEntry> {
-        let entry = self
-            .entries()
-            .unwrap_or_else(|_| vec![])
-            .into_iter()
-            .find(|entry| entry.name().eq(entry_name))?;
-
-        if open {
-            let Ok(filename) = CString::new(entry.name()) else {
-                return None;
-            };
+    pub fn entry_by_index(&self, index: u64, open: bool) -> ZipResult<ZipEntry> {
+        let (_guard, zip_file) = self.archive()?;
 
-            let zip_file = self.file?;
-            let file = unsafe { zip_fopen(zip_file, filename.as_ptr(), ZIP_FL_ENC_GUESS) };
+        let name = unsafe {
+            let name = zip_get_name(zip_file, index, ZIP_FL_ENC_GUESS);
+            if name.is_null() {
+                return Err(ZipError::from_archive(zip_file));
+            }
+            CStr::from_ptr(name).to_string_lossy().into_owned()
+        };
 
+        let file = if open {
+            let file = unsafe { zip_fopen_index(zip_file, index, 0) };
             if file.is_null() {
-                println!("Unable to open file in zip: {}", entry.name());
-                None
-            } else {
-                Some(ZipEntry::new(Some(file), &entry.name(), true))
+                return Err(unsafe { ZipError::from_archive(zip_file) });
             }
+            Some(file)
+        } else {
+            None
+        };
+
+        Ok(ZipEntry::in_archive(
+            Some(index),
+            file,
+            &name,
+            self.lock.clone(),
+        ))
+    }
+
+    pub fn get_entry(&self, entry_name: &str, open: bool) -> Option<ZipEntry> {
+        let index = self.index_of(entry_name, 0).ok()??;
+        self.entry_by_index(index, open).ok()
+    }
+
+    pub fn get_entry_with_password(&self, entry_name: &str, password: &str) -> ZipResult<ZipEntry> {
+        let (_guard, zip_file) = self.archive()?;
+        let c_name = CString::new(entry_name)?;
+        let c_password = CString::new(password)?;
+
+        let file = unsafe {
+            zip_fopen_encrypted(
+                zip_file,
+                c_name.as_ptr(),
+                ZIP_FL_ENC_GUESS,
+                c_password.as_ptr(),
+            )
+        };
+
+        if file.is_null() {
+            Err(unsafe { ZipError::from_archive(zip_file) })
         } else {
-            Some(entry)
+            let mut entry = ZipEntry::in_archive(None, Some(file), entry_name, self.lock.clone());
+            entry.set_password(password);
+            Ok(entry)
         }
     }
 
@@ -190,57 +443,175 @@ impl ZipFile {
             return Ok(());
         }
 
+        let _guard = self.lock.lock();
         let file = match self.file {
-            None => return Err("Zip file not open".into()),
+            None => return Err(ZipError::NotOpen),
             Some(file) => file,
         };
 
-        let error = unsafe {
-            let error = zip_strerror(file);
-            CStr::from_ptr(error).to_str()?
-        };
+        Err(unsafe { ZipError::from_archive(file) })
+    }
+
+    /// Looks an entry up with `zip_name_locate`. `flags` accepts `ZIP_FL_NOCASE`,
+    /// `ZIP_FL_NODIR` and the `ZIP_FL_ENC_*` name encodings.
+    pub fn index_of(&self, name: &str, flags: zip_flags_t) -> ZipResult<Option<u64>> {
+        let (_guard, zip_file) = self.archive()?;
+        let c_name = CString::new(name)?;
+
+        let index = unsafe { zip_name_locate(zip_file, c_name.as_ptr(), flags) };
+        match zip_uint64_t::try_from(index) {
+            Ok(index) => Ok(Some(index)),
+            Err(_) => match unsafe { ZipError::from_archive(zip_file) } {
+                ZipError::NoEntry => Ok(None),
+                error => Err(error),
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        Err(String::from(error).into())
+    pub fn iter<'a>(&'a self) -> Entries<'a> {
+        Entries::new(self.file, self.lock.clone())
     }
 
-    pub fn open(file: &Path, create: bool) -> Result<Self, String> {
-        let zip_file;
-        let location: &str = file.to_str().unwrap();
-        let c_src = CString::new(location).map_err(|error| error.to_string())?;
+    /// Whether closing the archive would write anything, as far as libzip lets
+    /// the entries and the archive comment be compared with their originals.
+    /// Unlike [`ZipFile::pending_changes`] this also notices metadata edits
+    /// such as a new compression method or mtime.
+    pub fn is_modified(&self) -> ZipResult<bool> {
+        let (_guard, zip_file) = self.archive()?;
+
         unsafe {
-            let mut zip_file_err = 0i32;
-            let flags = if create { ZIP_CREATE as c_int } else { 0 };
-            zip_file = zip_open(c_src.as_ptr(), flags, &mut zip_file_err as *mut c_int);
-
-            if zip_file.is_null() {
-                match zip_file_err as u32 {
-                    ZIP_ER_EXISTS => {
-                        Err("The file specified by path exists and ZIP_EXCL is set.".into())
-                    }
-                    ZIP_ER_INCONS => {
-                        Err("Inconsistencies were found in the file specified by path..".into())
-                    }
-                    ZIP_ER_INVAL => Err("The path argument is NULL".into()),
-                    ZIP_ER_MEMORY => Err("Required memory could not be allocated".into()),
-                    ZIP_ER_NOENT => Err(
-                        "The file specified by path does not exist and ZIP_CREATE is not set"
-                            .into(),
-                    ),
-                    ZIP_ER_NOZIP => Err("The file specified by path is not a zip archive".into()),
-                    ZIP_ER_OPEN => Err("The file specified by path could not be opened".into()),
-                    ZIP_ER_READ => Err("A read error ocurred".into()),
-                    ZIP_ER_SEEK => Err("The file specified by path does not allow seeks".into()),
-                    _ => Err("Unexpected error while trying to open the zip".into()),
+            let entries = zip_get_num_entries(zip_file, 0);
+
+            for index in 0..entries.max(0) as zip_uint64_t {
+                let current = metadata::stat_index(zip_file, index, 0);
+                let original =
+                    metadata::stat_index(zip_file, index, ZIP_FL_UNCHANGED as zip_flags_t);
+                match (current, original) {
+                    (Ok(current), Ok(original)) if current == original => {}
+                    // Entries added and then reverted stay behind as empty slots.
+                    (Err(_), Err(_)) => {}
+                    _ => return Ok(true),
                 }
+            }
+
+            let mut current_len = 0;
+            let mut original_len = 0;
+            let current = zip_get_archive_comment(zip_file, &mut current_len, 0);
+            let original = zip_get_archive_comment(
+                zip_file,
+                &mut original_len,
+                ZIP_FL_UNCHANGED as zip_flags_t,
+            );
+
+            Ok(current != original || current_len != original_len)
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        let _guard = self.lock.lock();
+        match self.file {
+            Some(zip_file) => unsafe { zip_get_num_entries(zip_file, 0).max(0) as u64 },
+            None => 0,
+        }
+    }
+
+    pub fn new_in_memory() -> Result<Self, ZipError> {
+        Self::open_buffer(Vec::new(), (ZIP_CREATE | ZIP_TRUNCATE) as c_int)
+    }
+
+    /// Calls `callback` with the completed fraction, from `0.0` to `1.0`, while
+    /// [`ZipFile::close`] writes the archive. A new report is only made once the
+    /// fraction has grown by at least `precision`. Replaces any earlier callback.
+    pub fn on_progress(
+        &self,
+        precision: f64,
+        callback: impl FnMut(f64) + Send + 'static,
+    ) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        progress::register_progress(zip_file, precision, Box::new(callback))
+    }
+
+    pub fn open_with_flags(file: &Path, flags: c_int) -> Result<Self, ZipError> {
+        let c_src = path_to_cstring(file)?;
+
+        let mut zip_file_err = 0i32;
+        let zip_file = unsafe { zip_open(c_src.as_ptr(), flags, &mut zip_file_err as *mut c_int) };
+
+        if zip_file.is_null() {
+            Err(ZipError::from_code(zip_file_err))
+        } else {
+            Ok(Self {
+                file: Some(zip_file),
+                filename: PathBuf::from(file),
+                source: None,
+                buffer: None,
+                drop_policy: DropPolicy::default(),
+                lock: ArchiveLock::new(zip_file),
+            })
+        }
+    }
+
+    fn open_buffer(data: Vec<u8>, flags: c_int) -> Result<Self, ZipError> {
+        let source = unsafe {
+            let mut error = std::mem::MaybeUninit::<zip_error_t>::uninit();
+            zip_error_init(error.as_mut_ptr());
+
+            let source = zip_source_buffer_create(
+                data.as_ptr() as _,
+                data.len() as zip_uint64_t,
+                0,
+                error.as_mut_ptr(),
+            );
+            let result = if source.is_null() {
+                Err(ZipError::from_zip_error(error.as_ptr()))
+            } else {
+                Ok(source)
+            };
+
+            zip_error_fini(error.as_mut_ptr());
+            result?
+        };
+
+        let mut zip = Self::open_source(source, flags)?;
+        zip.buffer = Some(data);
+        Ok(zip)
+    }
+
+    pub(crate) fn open_source(source: *mut zip_source_t, flags: c_int) -> Result<Self, ZipError> {
+        unsafe {
+            let mut error = std::mem::MaybeUninit::<zip_error_t>::uninit();
+            zip_error_init(error.as_mut_ptr());
+
+            let zip_file = zip_open_from_source(source, flags, error.as_mut_ptr());
+            let result = if zip_file.is_null() {
+                zip_source_free(source);
+                Err(ZipError::from_zip_error(error.as_ptr()))
             } else {
+                zip_source_keep(source);
                 Ok(Self {
                     file: Some(zip_file),
-                    filename: PathBuf::from(file),
+                    filename: PathBuf::new(),
+                    source: Some(source),
+                    buffer: None,
+                    drop_policy: DropPolicy::default(),
+                    lock: ArchiveLock::new(zip_file),
                 })
-            }
+            };
+
+            zip_error_fini(error.as_mut_ptr());
+            result
         }
     }
 
+    pub fn options() -> OpenOptions {
+        OpenOptions::new()
+    }
+
     pub fn pack_file(batch_name: String, src: &str, filename: String) {
         let c_batch_name = CString::new(batch_name).unwrap();
         let c_src = CString::new(src).unwrap();
@@ -267,38 +638,260 @@ impl ZipFile {
         }
     }
 
+    pub(crate) fn set_external_attributes(
+        &self,
+        index: u64,
+        opsys: u8,
+        attributes: u32,
+    ) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        let result =
+            unsafe { zip_file_set_external_attributes(zip_file, index, 0, opsys, attributes) };
+        self.get_error(result as i64)
+    }
+
+    pub(crate) fn set_mtime(&self, index: u64, modified: SystemTime) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        let result =
+            unsafe { zip_file_set_mtime(zip_file, index, metadata::dos_time_t(modified), 0) };
+        self.get_error(result as i64)
+    }
+
+    /// Setting `token` while [`ZipFile::close`] runs aborts the commit with
+    /// [`ZipError::Cancelled`]. The original archive is left untouched and this
+    /// `ZipFile` stays open, with its pending changes, so it can be closed again
+    /// or discarded. Readers passed to [`ZipFile::add_reader`] cannot be read a
+    /// second time, so the retry fails as well if one of them was already used.
+    pub fn set_cancel_token(&self, token: Arc<AtomicBool>) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        progress::register_cancel(zip_file, token)
+    }
+
+    /// Lists the entries added, replaced, renamed or deleted since the archive
+    /// was opened.
+    pub fn pending_changes(&self) -> ZipResult<Vec<PendingChange>> {
+        let (_guard, zip_file) = self.archive()?;
+
+        changes::pending_changes(zip_file)
+    }
+
+    /// The archive comment exactly as stored, without any conversion.
+    pub fn raw_comment(&self) -> ZipResult<Vec<u8>> {
+        let (_guard, zip_file) = self.archive()?;
+
+        comment::archive_comment(zip_file, ZIP_FL_ENC_RAW as zip_flags_t)
+    }
+
+    /// Renames entry `old` to `new`, keeping its index, data, comment and extra
+    /// fields. Fails with [`ZipError::Exists`] if another entry is already
+    /// called `new`.
+    pub fn rename_entry(&self, old: &str, new: &str) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(old, 0)?.ok_or(ZipError::NoEntry)?;
+        let c_new = CString::new(new)?;
+
+        let result =
+            unsafe { zip_file_rename(zip_file, index, c_new.as_ptr(), ZIP_FL_ENC_UTF_8 as _) };
+        self.get_error(result as i64)
+    }
+
+    /// Reverts every staged change to the entries (`zip_unchange_all`), along
+    /// with the archive comment.
+    pub fn revert_all(&self) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        let result = unsafe { zip_unchange_all(zip_file) };
+        self.get_error(result as i64)
+    }
+
+    /// Reverts archive level changes, currently only the comment
+    /// (`zip_unchange_archive`).
+    pub fn revert_archive_metadata(&self) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+
+        let result = unsafe { zip_unchange_archive(zip_file) };
+        self.get_error(result as i64)
+    }
+
+    /// Reverts every staged change to one entry (`zip_unchange`). `name` may be
+    /// its current name or, for renamed and deleted entries, the original one.
+    pub fn revert_entry(&self, name: &str) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = match self.index_of(name, 0)? {
+            Some(index) => Some(index),
+            None => self.index_of(name, ZIP_FL_UNCHANGED as zip_flags_t)?,
+        }
+        .ok_or(ZipError::NoEntry)?;
+
+        let result = unsafe { zip_unchange(zip_file, index) };
+        self.get_error(result as i64)
+    }
+
+    /// Replaces the archive comment; an empty string removes it. libzip only
+    /// accepts ASCII or UTF-8 here, the format has no way to mark an archive
+    /// comment as CP437.
+    pub fn set_comment(&self, comment: &str) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let len = comment::comment_len(comment.as_bytes())?;
+
+        let result = unsafe { zip_set_archive_comment(zip_file, comment.as_ptr() as _, len) };
+        self.get_error(result as i64)
+    }
+
+    pub fn set_drop_policy(&mut self, policy: DropPolicy) {
+        self.drop_policy = policy;
+    }
+
+    /// Replaces the comment of entry `name`; an empty comment removes it.
+    pub fn set_entry_comment(
+        &self,
+        name: &str,
+        comment: impl AsRef<[u8]>,
+        encoding: CommentEncoding,
+    ) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+        let comment = comment.as_ref();
+        let len = comment::comment_len(comment)?;
+
+        let result = unsafe {
+            zip_file_set_comment(
+                zip_file,
+                index,
+                comment.as_ptr() as _,
+                len,
+                encoding.flags(),
+            )
+        };
+        self.get_error(result as i64)
+    }
+
+    /// Sets the modification time of entry `name`. ZIP entries keep it as an
+    /// MS-DOS timestamp in local time: seconds are rounded down to an even
+    /// number, and times before 1980-01-01 or after 2107-12-31 are clamped to
+    /// those dates. Store an [`ExtendedTimestamp`] or [`NtfsTimes`] extra field
+    /// as well when the exact time matters.
+    pub fn set_entry_mtime(&self, name: &str, modified: SystemTime) -> ZipResult<()> {
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        self.set_mtime(index, modified)
+    }
+
+    /// Stores `data` as the extra field `id` of entry `name`, replacing the
+    /// first existing field with that id at each location.
+    pub fn set_extra_field(
+        &self,
+        name: &str,
+        id: u16,
+        data: &[u8],
+        location: ExtraFieldLocation,
+    ) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        extra::set_extra_field(zip_file, index, id, data, location)
+    }
+
+    pub fn set_default_password(&self, password: Option<&str>) -> ZipResult<()> {
+        let (_guard, zip_file) = self.archive()?;
+        let password = password.map(CString::new).transpose()?;
+        let password_ptr = password.as_ref().map_or(std::ptr::null(), |p| p.as_ptr());
+
+        let result = unsafe { zip_set_default_password(zip_file, password_ptr) };
+        self.get_error(result as i64)
+    }
+
     pub fn file_stat(&self, filename: &str) -> ZipResult<Box<zip_stat>> {
+        let _guard = self.lock.lock();
         let file = match self.file {
-            None => return Err("No zip is open".into()),
+            None => return Err(ZipError::NotOpen),
             Some(file) => file,
         };
 
-        let name = CString::new("")?;
-        let file_stat = zip_stat_t {
-            valid: 0,
-            name: name.as_ptr(),
-            index: 0,
-            size: 0,
-            comp_size: 0,
-            mtime: 0,
-            crc: 0,
-            comp_method: 0,
-            encryption_method: 0,
-            flags: 0,
-        };
-        let file_stat = Box::new(file_stat);
-        let file_stat = Box::into_raw(file_stat);
         let filename = CString::new(filename)?;
 
         let stat = unsafe {
-            zip_stat_init(file_stat);
-            let result = zip_stat(file, filename.as_ptr(), ZIP_FL_ENC_GUESS, file_stat);
+            let mut file_stat = Box::new(std::mem::zeroed::<zip_stat_t>());
+            zip_stat_init(file_stat.as_mut());
+            let result = zip_stat(
+                file,
+                filename.as_ptr(),
+                ZIP_FL_ENC_GUESS,
+                file_stat.as_mut(),
+            );
             self.get_error(result as i64)?;
-            Box::from_raw(file_stat)
+            file_stat
         };
 
         Ok(stat)
     }
+
+    pub fn metadata(&self, filename: &str) -> ZipResult<EntryMetadata> {
+        let (_guard, zip_file) = self.archive()?;
+        metadata::stat_name(zip_file, filename, ZIP_FL_ENC_GUESS)
+    }
+
+    fn apply_drop_policy(&mut self) {
+        match self.drop_policy {
+            DropPolicy::Commit => {
+                if let Err(error) = self.close() {
+                    println!("Unable to close zip file: {:?}", error);
+                }
+            }
+            DropPolicy::Discard => self.discard(),
+            DropPolicy::PanicIfDirty => {
+                let dirty = self.is_modified().unwrap_or(false);
+                self.discard();
+                self.release_source();
+
+                // Panicking while already unwinding would abort the process.
+                if dirty && !std::thread::panicking() {
+                    panic!("ZipFile {} dropped with uncommitted changes", self);
+                }
+                return;
+            }
+        }
+        self.release_source();
+    }
+
+    fn replace_zip_source(&self, name: &str, zip_source: *mut zip_source_t) -> ZipResult<()> {
+        let index = match self.index_of(name, 0) {
+            Ok(Some(index)) => index,
+            result => {
+                unsafe { zip_source_free(zip_source) };
+                return Err(result.err().unwrap_or(ZipError::NoEntry));
+            }
+        };
+        let (_guard, zip_file) = self.archive()?;
+
+        unsafe {
+            if zip_file_replace(zip_file, index, zip_source, 0) != 0 {
+                zip_source_free(zip_source);
+                return Err(ZipError::from_archive(zip_file));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The archive handle, along with the lock that keeps entries read on other
+    /// threads out of libzip while it is in use.
+    fn archive(&self) -> ZipResult<(ArchiveGuard, *mut zip_t)> {
+        let guard = self.lock.lock();
+        let zip_file = self.file.ok_or(ZipError::NotOpen)?;
+
+        Ok((guard, zip_file))
+    }
+
+    fn release_source(&mut self) {
+        if let Some(source) = self.source.take() {
+            unsafe { zip_source_free(source) };
+        }
+        self.buffer = None;
+    }
 }
 
 #[cfg_attr(feature = "faux", faux::methods)]
@@ -309,11 +902,140 @@ impl Display for ZipFile {
     }
 }
 
+// A `ZipFile` owns its archive handle outright: it is neither `Clone` nor `Sync`,
+// so the handle is only ever used by the thread currently holding it, and every
+// source handed to libzip is required to be `Send`.
+unsafe impl Send for ZipFile {}
+
+impl ZipFile {
+    /// Recursively adds the contents of `src_dir` under `prefix`, creating
+    /// directory entries and keeping each file's mtime and Unix mode.
+    pub fn add_dir_all(
+        &self,
+        src_dir: &Path,
+        prefix: &str,
+        options: &AddDirOptions,
+    ) -> ZipResult<()> {
+        walk::add_dir_all(self, src_dir, prefix, options)
+    }
+
+    pub fn add_source(
+        &self,
+        source: RustSource<impl Read + Seek + Send + 'static>,
+        filename: &str,
+        options: &EntryOptions,
+    ) -> ZipResult<()> {
+        options.validate()?;
+        let zip_source = source.into_raw()?;
+
+        self.add_zip_source(zip_source, filename, options)
+    }
+
+    pub fn extract_all(&self, dest: &Path, options: &ExtractOptions) -> ZipResult<()> {
+        extract::extract_all(self, dest, options)
+    }
+
+    pub fn from_reader(reader: impl Read + Seek + Send + 'static) -> Result<Self, ZipError> {
+        Self::options().open_reader(reader)
+    }
+
+    pub fn from_writable(
+        target: impl Read + Write + Seek + SetLen + Send + 'static,
+    ) -> Result<Self, ZipError> {
+        Self::options().open_writable(target)
+    }
+
+    pub fn into_bytes(mut self) -> ZipResult<Vec<u8>> {
+        self.close()?;
+        self.bytes()
+    }
+
+    pub fn open(file: &Path, create: bool) -> Result<Self, ZipError> {
+        let options = Self::options();
+        let options = if create { options.create() } else { options };
+
+        options.open(file)
+    }
+
+    /// Swaps the data of entry `name` for `source` (`zip_file_replace`). The
+    /// entry keeps its index, name, comment and extra fields.
+    pub fn replace_entry(
+        &self,
+        name: &str,
+        source: RustSource<impl Read + Seek + Send + 'static>,
+    ) -> ZipResult<()> {
+        let zip_source = source.into_raw()?;
+
+        self.replace_zip_source(name, zip_source)
+    }
+
+    /// Reads and parses extra field `T` of entry `name`. `None` when the field is
+    /// missing or malformed.
+    pub fn typed_extra_field<T: ExtraFieldData>(
+        &self,
+        name: &str,
+        location: ExtraFieldLocation,
+    ) -> ZipResult<Option<T>> {
+        Ok(self
+            .extra_field(name, T::ID, location)?
+            .and_then(|data| T::parse(&data)))
+    }
+
+    pub fn set_typed_extra_field<T: ExtraFieldData>(
+        &self,
+        name: &str,
+        field: &T,
+        location: ExtraFieldLocation,
+    ) -> ZipResult<()> {
+        for &location in location.split() {
+            self.set_extra_field(name, T::ID, &field.encode(location), location)?;
+        }
+
+        Ok(())
+    }
+
+    /// The target of symlink entry `name`, or `None` if the entry is not a
+    /// symlink.
+    pub fn symlink_target(&self, name: &str) -> ZipResult<Option<String>> {
+        if !self.metadata(name)?.is_symlink() {
+            return Ok(None);
+        }
+
+        let index = self.index_of(name, 0)?.ok_or(ZipError::NoEntry)?;
+        let mut target = String::new();
+        self.entry_by_index(index, true)?
+            .read_to_string(&mut target)?;
+
+        Ok(Some(target))
+    }
+
+    /// Runs `f` and commits the archive with [`ZipFile::close`] only if it
+    /// returns `Ok`. On error, or if `f` panics, every pending change is
+    /// discarded instead. The archive is closed either way.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> ZipResult<T>) -> ZipResult<T> {
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(value)) => match self.close() {
+                Ok(()) => Ok(value),
+                Err(error) => {
+                    self.discard();
+                    Err(error)
+                }
+            },
+            Ok(Err(error)) => {
+                self.discard();
+                Err(error)
+            }
+            Err(panic) => {
+                self.discard();
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+}
+
 impl Drop for ZipFile {
     fn drop(&mut self) {
-        if let Err(error) = self.close() {
-            println!("Unable to close zip file: {:?}", error);
-        }
+        self.apply_drop_policy();
     }
 }
 
@@ -324,14 +1046,55 @@ impl ZipEntry {
             file,
             name: name.to_string(),
             is_open,
+            index: None,
+            seek_emulation: false,
+            password: None,
+            lock: None,
+            #[cfg(feature = "tokio")]
+            pending: None,
+            #[cfg(feature = "tokio")]
+            read_buf: Vec::new(),
+            #[cfg(feature = "tokio")]
+            read_pos: 0,
+        }
+    }
+
+    pub(crate) fn in_archive(
+        index: Option<zip_uint64_t>,
+        file: Option<*mut zip_file_t>,
+        name: &str,
+        lock: Arc<ArchiveLock>,
+    ) -> Self {
+        Self {
+            file,
+            name: name.to_string(),
+            is_open: file.is_some(),
+            index,
+            seek_emulation: false,
+            password: None,
+            lock: Some(lock),
+            #[cfg(feature = "tokio")]
+            pending: None,
+            #[cfg(feature = "tokio")]
+            read_buf: Vec::new(),
+            #[cfg(feature = "tokio")]
+            read_pos: 0,
         }
     }
 
+    /// Remembers the password the entry was opened with, so that reopening it
+    /// for a backward seek can decrypt it again.
+    pub(crate) fn set_password(&mut self, password: &str) {
+        self.password = Some(Password(password.to_string()));
+    }
+
     pub fn close(&mut self) {
         if !self.is_open {
             return;
         }
 
+        let _guard = self.guard();
+
         if let Some(file) = self.file {
             unsafe {
                 zip_fclose(file);
@@ -340,14 +1103,151 @@ impl ZipEntry {
         }
     }
 
+    /// The entry comment, converted to UTF-8 when stored as CP437. Empty when
+    /// the entry has none.
+    pub fn comment(&self) -> ZipResult<String> {
+        self.comment_with_flags(ZIP_FL_ENC_GUESS as zip_flags_t)
+            .map(comment::to_text)
+    }
+
+    pub fn raw_comment(&self) -> ZipResult<Vec<u8>> {
+        self.comment_with_flags(ZIP_FL_ENC_RAW as zip_flags_t)
+    }
+
+    fn comment_with_flags(&self, flags: zip_flags_t) -> ZipResult<Vec<u8>> {
+        let (_guard, archive) = self.archive()?;
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                let name = CString::new(self.name.as_str())?;
+                let index = unsafe { zip_name_locate(archive, name.as_ptr(), 0) };
+                zip_uint64_t::try_from(index)
+                    .map_err(|_| unsafe { ZipError::from_archive(archive) })?
+            }
+        };
+
+        comment::entry_comment(archive, index, flags)
+    }
+
+    /// Lets [`Seek`] work on entries libzip cannot seek in (compressed or
+    /// encrypted data) by decompressing and discarding everything up to the
+    /// target. Seeking backwards reopens the entry and starts over.
+    pub fn emulate_seek(&mut self, enabled: bool) {
+        self.seek_emulation = enabled;
+    }
+
+    pub fn is_seekable(&self) -> bool {
+        let _guard = self.guard();
+        match self.file {
+            Some(file) => unsafe { zip_file_is_seekable(file) == 1 },
+            None => false,
+        }
+    }
+
+    pub fn metadata(&self) -> ZipResult<EntryMetadata> {
+        let (_guard, archive) = self.archive()?;
+
+        match self.index {
+            Some(index) => metadata::stat_index(archive, index, 0),
+            None => metadata::stat_name(archive, &self.name, ZIP_FL_ENC_GUESS),
+        }
+    }
+
     pub fn name(&self) -> String {
         self.name.to_string()
     }
+
+    /// Entries made by [`ZipEntry::new`] have no archive lock to take.
+    fn guard(&self) -> Option<ArchiveGuard> {
+        self.lock.as_ref().map(|lock| lock.lock())
+    }
+
+    /// The handle of the archive this entry belongs to, while that archive is
+    /// still open, along with its lock.
+    fn archive(&self) -> ZipResult<(ArchiveGuard, *mut zip_t)> {
+        let lock = self.lock.as_ref().ok_or(ZipError::NotOpen)?;
+        let guard = lock.lock();
+        let archive = lock.archive().ok_or(ZipError::NotOpen)?;
+
+        Ok((guard, archive))
+    }
+
+    fn reopen(&mut self) -> ZipResult<*mut zip_file_t> {
+        let (_guard, archive) = self.archive()?;
+        let password = self
+            .password
+            .as_ref()
+            .map(|password| CString::new(password.0.as_str()));
+        let password = password.transpose()?;
+        self.close();
+        self.file = None;
+
+        let file = match (self.index, &password) {
+            (Some(index), None) => unsafe { zip_fopen_index(archive, index, 0) },
+            (Some(index), Some(password)) => unsafe {
+                zip_fopen_index_encrypted(archive, index, 0, password.as_ptr())
+            },
+            (None, password) => {
+                let name = CString::new(self.name.as_str())?;
+                match password {
+                    None => unsafe { zip_fopen(archive, name.as_ptr(), ZIP_FL_ENC_GUESS) },
+                    Some(password) => unsafe {
+                        zip_fopen_encrypted(
+                            archive,
+                            name.as_ptr(),
+                            ZIP_FL_ENC_GUESS,
+                            password.as_ptr(),
+                        )
+                    },
+                }
+            }
+        };
+
+        if file.is_null() {
+            return Err(unsafe { ZipError::from_archive(archive) });
+        }
+
+        self.file = Some(file);
+        self.is_open = true;
+        Ok(file)
+    }
+
+    fn emulated_seek(&mut self, file: *mut zip_file_t, pos: SeekFrom) -> std::io::Result<u64> {
+        let current = unsafe { zip_ftell(file) };
+        if current < 0 {
+            return Err(unsafe { ZipError::from_file(file) }.into());
+        }
+
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => (current as u64).checked_add_signed(offset),
+            SeekFrom::End(offset) => {
+                let size = self
+                    .metadata()?
+                    .size
+                    .ok_or(ZipError::OperationNotSupported)?;
+                size.checked_add_signed(offset)
+            }
+        };
+        let Some(target) = target else {
+            return Err(ZipError::Invalid.into());
+        };
+
+        let mut position = current as u64;
+        if target < position {
+            self.reopen()?;
+            position = 0;
+        }
+
+        let skipped = std::io::copy(&mut self.take(target - position), &mut std::io::sink())?;
+        Ok(position + skipped)
+    }
 }
 
 #[cfg_attr(feature = "faux", faux::methods)]
 impl std::io::Read for ZipEntry {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let _guard = self.guard();
         match self.file {
             Some(zip_file) => {
                 let block_size = buf.len() as u64;
@@ -357,16 +1257,48 @@ impl std::io::Read for ZipEntry {
                 if bytes_readed >= 0 {
                     Ok(bytes_readed as usize)
                 } else {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "Unable to read data",
-                    ))
+                    Err(unsafe { ZipError::from_file(zip_file) }.into())
                 }
             }
-            None => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Zip file is not open",
-            )),
+            None => Err(ZipError::NotOpen.into()),
+        }
+    }
+}
+
+#[cfg_attr(feature = "faux", faux::methods)]
+impl Seek for ZipEntry {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let _guard = self.guard();
+        let file = self.file.ok_or(ZipError::NotOpen)?;
+
+        if !self.is_seekable() {
+            if self.seek_emulation {
+                return self.emulated_seek(file, pos);
+            }
+
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Entry is compressed or encrypted and cannot be seeked",
+            ));
+        }
+
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as zip_int64_t, SEEK_SET),
+            SeekFrom::Current(offset) => (offset, SEEK_CUR),
+            SeekFrom::End(offset) => (offset, SEEK_END),
+        };
+
+        unsafe {
+            if zip_fseek(file, offset, whence as c_int) < 0 {
+                return Err(ZipError::from_file(file).into());
+            }
+
+            let position = zip_ftell(file);
+            if position < 0 {
+                return Err(ZipError::from_file(file).into());
+            }
+
+            Ok(position as u64)
         }
     }
 }
@@ -374,33 +1306,109 @@ impl std::io::Read for ZipEntry {
 #[cfg_attr(feature = "faux", faux::methods)]
 #[cfg(feature = "tokio")]
 impl tokio::io::AsyncRead for ZipEntry {
+    /// `zip_fread` decompresses on the calling thread, so every read is handed
+    /// to tokio's blocking pool. Only one read is in flight at a time and a new
+    /// one is only started once the previous data has been consumed.
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
+        cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
-        match self.file {
-            Some(zip_file) => {
-                let size = buf.remaining();
-                let mut buffer: Vec<u8> = Vec::with_capacity(size);
-                let bytes_read = unsafe {
-                    zip_fread(
-                        zip_file,
-                        buffer.as_mut_ptr() as *mut c_void,
-                        buffer.capacity() as u64,
-                    )
+        use std::future::Future;
+        use std::task::Poll;
+
+        let this = self.get_mut();
+
+        loop {
+            if this.read_pos < this.read_buf.len() {
+                let available = &this.read_buf[this.read_pos..];
+                let len = available.len().min(buf.remaining());
+                buf.put_slice(&available[..len]);
+                this.read_pos += len;
+                return Poll::Ready(Ok(()));
+            }
+
+            if let Some(pending) = this.pending.as_mut() {
+                let result = match std::pin::Pin::new(pending).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(result) => result,
                 };
+                this.pending = None;
+
+                let (file, data) = result.map_err(std::io::Error::other)?;
+                this.file = Some(file.into_raw());
+                this.read_buf = data?;
+                this.read_pos = 0;
 
-                if bytes_read > 0 {
-                    buf.put_slice(buffer.as_slice());
+                if this.read_buf.is_empty() {
+                    return Poll::Ready(Ok(()));
                 }
+                continue;
+            }
 
-                std::task::Poll::Ready(Ok(()))
+            if buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
             }
-            None => std::task::Poll::Ready(Err(tokio::io::Error::new(
-                tokio::io::ErrorKind::Other,
-                "Zip file is not open",
-            ))),
+
+            let file = OwnedFile {
+                file: Some(this.file.take().ok_or(ZipError::NotOpen)?),
+                lock: this.lock.clone(),
+            };
+            let len = buf.remaining().min(ASYNC_READ_CHUNK);
+            this.pending = Some(tokio::task::spawn_blocking(move || {
+                let raw = file.file.unwrap_or_else(null_mut);
+                let mut data = vec![0u8; len];
+
+                let guard = file.lock.as_ref().map(|lock| lock.lock());
+                let bytes_read =
+                    unsafe { zip_fread(raw, data.as_mut_ptr() as *mut c_void, len as u64) };
+                let result = if bytes_read >= 0 {
+                    data.truncate(bytes_read as usize);
+                    Ok(data)
+                } else {
+                    Err(unsafe { ZipError::from_file(raw) }.into())
+                };
+                drop(guard);
+
+                (file, result)
+            }));
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+const ASYNC_READ_CHUNK: usize = 1024 * 1024;
+
+#[cfg(feature = "tokio")]
+type PendingRead = tokio::task::JoinHandle<(OwnedFile, std::io::Result<Vec<u8>>)>;
+
+/// An entry handle lent to the blocking pool. If the entry is dropped while a
+/// read is in flight, the handle is closed when the read completes. The
+/// archive lock is taken around every libzip call, so the owning thread cannot
+/// use, close or drop the archive while the read runs.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+struct OwnedFile {
+    file: Option<*mut zip_file_t>,
+    lock: Option<Arc<ArchiveLock>>,
+}
+
+#[cfg(feature = "tokio")]
+unsafe impl Send for OwnedFile {}
+
+#[cfg(feature = "tokio")]
+impl OwnedFile {
+    fn into_raw(mut self) -> *mut zip_file_t {
+        self.file.take().unwrap_or_else(null_mut)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Drop for OwnedFile {
+    fn drop(&mut self) {
+        if let Some(file) = self.file.take() {
+            let _guard = self.lock.as_ref().map(|lock| lock.lock());
+            unsafe { zip_fclose(file) };
         }
     }
 }
@@ -410,3 +1418,42 @@ impl Drop for ZipEntry {
         self.close();
     }
 }
+
+fn path_to_cstring(path: &Path) -> ZipResult<CString> {
+    let Some(path_str) = path.to_str() else {
+        return Err(ZipError::InvalidPath(path.to_path_buf()));
+    };
+
+    Ok(CString::new(path_str)?)
+}
+
+fn read_source(source: *mut zip_source_t) -> ZipResult<Vec<u8>> {
+    unsafe {
+        if zip_source_open(source) < 0 {
+            return Err(ZipError::from_zip_error(zip_source_error(source)));
+        }
+
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 8192];
+        loop {
+            let bytes_read = zip_source_read(
+                source,
+                chunk.as_mut_ptr() as *mut c_void,
+                chunk.len() as zip_uint64_t,
+            );
+
+            match bytes_read {
+                0 => break,
+                n if n < 0 => {
+                    let error = ZipError::from_zip_error(zip_source_error(source));
+                    zip_source_close(source);
+                    return Err(error);
+                }
+                n => data.extend_from_slice(&chunk[..n as usize]),
+            }
+        }
+
+        zip_source_close(source);
+        Ok(data)
+    }
+}
@@ -0,0 +1,89 @@
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+use crate::zip_t;
+
+/// Serialises every call into one libzip archive. Archives are not thread-safe,
+/// and an entry read on tokio's blocking pool would otherwise race with the
+/// thread that owns the [`ZipFile`](crate::ZipFile). The lock is re-entrant, as
+/// `ZipFile` methods build on each other.
+///
+/// The lock also holds the archive handle for the entries that outlive their
+/// `ZipFile`: it is cleared under the lock once the archive is closed, so an
+/// entry never reaches a freed archive.
+#[derive(Debug, Default)]
+pub(crate) struct ArchiveLock {
+    state: Mutex<LockState>,
+    released: Condvar,
+    archive: AtomicPtr<zip_t>,
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    owner: Option<ThreadId>,
+    depth: usize,
+}
+
+/// Held while libzip is being called; must be dropped on the thread that
+/// acquired it.
+#[derive(Debug)]
+pub(crate) struct ArchiveGuard {
+    lock: Arc<ArchiveLock>,
+}
+
+impl ArchiveLock {
+    pub(crate) fn new(archive: *mut zip_t) -> Arc<Self> {
+        Arc::new(Self {
+            archive: AtomicPtr::new(archive),
+            ..Self::default()
+        })
+    }
+
+    /// The archive handle, `None` once the archive has been closed. Only to be
+    /// used while the lock is held.
+    pub(crate) fn archive(&self) -> Option<*mut zip_t> {
+        let archive = self.archive.load(Ordering::Acquire);
+        (!archive.is_null()).then_some(archive)
+    }
+
+    /// Forgets the archive handle; called with the lock held, right after the
+    /// archive is closed or discarded.
+    pub(crate) fn release(&self) {
+        self.archive.store(null_mut(), Ordering::Release);
+    }
+
+    pub(crate) fn lock(self: &Arc<Self>) -> ArchiveGuard {
+        let current = thread::current().id();
+        let mut state = self.state();
+
+        while state.owner.is_some_and(|owner| owner != current) {
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(|error| error.into_inner());
+        }
+        state.owner = Some(current);
+        state.depth += 1;
+
+        ArchiveGuard { lock: self.clone() }
+    }
+
+    fn state(&self) -> MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Drop for ArchiveGuard {
+    fn drop(&mut self) {
+        let mut state = self.lock.state();
+        state.depth -= 1;
+
+        if state.depth == 0 {
+            state.owner = None;
+            drop(state);
+            self.lock.released.notify_all();
+        }
+    }
+}
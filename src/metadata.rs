@@ -0,0 +1,406 @@
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::*;
+
+pub(crate) const S_IFMT: u32 = 0o170000;
+pub(crate) const S_IFDIR: u32 = 0o040000;
+pub(crate) const S_IFREG: u32 = 0o100000;
+pub(crate) const S_IFLNK: u32 = 0o120000;
+pub(crate) const DOS_DIRECTORY: u32 = 0x10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionMethod {
+    Store,
+    Shrink,
+    Reduce1,
+    Reduce2,
+    Reduce3,
+    Reduce4,
+    Implode,
+    Deflate,
+    Deflate64,
+    PkwareImplode,
+    Bzip2,
+    Lzma,
+    Terse,
+    Lz77,
+    Lzma2,
+    Zstd,
+    Xz,
+    Jpeg,
+    WavPack,
+    Ppmd,
+    Unknown(zip_uint16_t),
+}
+
+impl CompressionMethod {
+    pub fn from_raw(method: zip_uint16_t) -> Self {
+        match method as u32 {
+            ZIP_CM_STORE => Self::Store,
+            ZIP_CM_SHRINK => Self::Shrink,
+            ZIP_CM_REDUCE_1 => Self::Reduce1,
+            ZIP_CM_REDUCE_2 => Self::Reduce2,
+            ZIP_CM_REDUCE_3 => Self::Reduce3,
+            ZIP_CM_REDUCE_4 => Self::Reduce4,
+            ZIP_CM_IMPLODE => Self::Implode,
+            ZIP_CM_DEFLATE => Self::Deflate,
+            ZIP_CM_DEFLATE64 => Self::Deflate64,
+            ZIP_CM_PKWARE_IMPLODE => Self::PkwareImplode,
+            ZIP_CM_BZIP2 => Self::Bzip2,
+            ZIP_CM_LZMA => Self::Lzma,
+            ZIP_CM_TERSE => Self::Terse,
+            ZIP_CM_LZ77 => Self::Lz77,
+            ZIP_CM_LZMA2 => Self::Lzma2,
+            ZIP_CM_ZSTD => Self::Zstd,
+            ZIP_CM_XZ => Self::Xz,
+            ZIP_CM_JPEG => Self::Jpeg,
+            ZIP_CM_WAVPACK => Self::WavPack,
+            ZIP_CM_PPMD => Self::Ppmd,
+            _ => Self::Unknown(method),
+        }
+    }
+
+    /// Whether the bundled libzip can decompress, or with `compress` also write,
+    /// entries using this method.
+    pub fn is_supported(&self, compress: bool) -> bool {
+        unsafe { zip_compression_method_supported(self.raw() as zip_int32_t, compress as _) != 0 }
+    }
+
+    pub fn raw(&self) -> zip_uint16_t {
+        let method = match self {
+            Self::Store => ZIP_CM_STORE,
+            Self::Shrink => ZIP_CM_SHRINK,
+            Self::Reduce1 => ZIP_CM_REDUCE_1,
+            Self::Reduce2 => ZIP_CM_REDUCE_2,
+            Self::Reduce3 => ZIP_CM_REDUCE_3,
+            Self::Reduce4 => ZIP_CM_REDUCE_4,
+            Self::Implode => ZIP_CM_IMPLODE,
+            Self::Deflate => ZIP_CM_DEFLATE,
+            Self::Deflate64 => ZIP_CM_DEFLATE64,
+            Self::PkwareImplode => ZIP_CM_PKWARE_IMPLODE,
+            Self::Bzip2 => ZIP_CM_BZIP2,
+            Self::Lzma => ZIP_CM_LZMA,
+            Self::Terse => ZIP_CM_TERSE,
+            Self::Lz77 => ZIP_CM_LZ77,
+            Self::Lzma2 => ZIP_CM_LZMA2,
+            Self::Zstd => ZIP_CM_ZSTD,
+            Self::Xz => ZIP_CM_XZ,
+            Self::Jpeg => ZIP_CM_JPEG,
+            Self::WavPack => ZIP_CM_WAVPACK,
+            Self::Ppmd => ZIP_CM_PPMD,
+            Self::Unknown(method) => return *method,
+        };
+
+        method as zip_uint16_t
+    }
+}
+
+/// The system an entry was created on, which decides how its external
+/// attributes are to be read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HostOs {
+    Dos,
+    Amiga,
+    OpenVms,
+    Unix,
+    VmCms,
+    AtariSt,
+    Os2,
+    Macintosh,
+    ZSystem,
+    Cpm,
+    WindowsNtfs,
+    Mvs,
+    Vse,
+    AcornRisc,
+    Vfat,
+    AlternateMvs,
+    BeOs,
+    Tandem,
+    Os400,
+    OsX,
+    Unknown(zip_uint8_t),
+}
+
+impl HostOs {
+    pub fn from_raw(opsys: zip_uint8_t) -> Self {
+        match opsys as u32 {
+            ZIP_OPSYS_DOS => Self::Dos,
+            ZIP_OPSYS_AMIGA => Self::Amiga,
+            ZIP_OPSYS_OPENVMS => Self::OpenVms,
+            ZIP_OPSYS_UNIX => Self::Unix,
+            ZIP_OPSYS_VM_CMS => Self::VmCms,
+            ZIP_OPSYS_ATARI_ST => Self::AtariSt,
+            ZIP_OPSYS_OS_2 => Self::Os2,
+            ZIP_OPSYS_MACINTOSH => Self::Macintosh,
+            ZIP_OPSYS_Z_SYSTEM => Self::ZSystem,
+            ZIP_OPSYS_CPM => Self::Cpm,
+            ZIP_OPSYS_WINDOWS_NTFS => Self::WindowsNtfs,
+            ZIP_OPSYS_MVS => Self::Mvs,
+            ZIP_OPSYS_VSE => Self::Vse,
+            ZIP_OPSYS_ACORN_RISC => Self::AcornRisc,
+            ZIP_OPSYS_VFAT => Self::Vfat,
+            ZIP_OPSYS_ALTERNATE_MVS => Self::AlternateMvs,
+            ZIP_OPSYS_BEOS => Self::BeOs,
+            ZIP_OPSYS_TANDEM => Self::Tandem,
+            ZIP_OPSYS_OS_400 => Self::Os400,
+            ZIP_OPSYS_OS_X => Self::OsX,
+            _ => Self::Unknown(opsys),
+        }
+    }
+
+    pub fn raw(&self) -> zip_uint8_t {
+        let opsys = match self {
+            Self::Dos => ZIP_OPSYS_DOS,
+            Self::Amiga => ZIP_OPSYS_AMIGA,
+            Self::OpenVms => ZIP_OPSYS_OPENVMS,
+            Self::Unix => ZIP_OPSYS_UNIX,
+            Self::VmCms => ZIP_OPSYS_VM_CMS,
+            Self::AtariSt => ZIP_OPSYS_ATARI_ST,
+            Self::Os2 => ZIP_OPSYS_OS_2,
+            Self::Macintosh => ZIP_OPSYS_MACINTOSH,
+            Self::ZSystem => ZIP_OPSYS_Z_SYSTEM,
+            Self::Cpm => ZIP_OPSYS_CPM,
+            Self::WindowsNtfs => ZIP_OPSYS_WINDOWS_NTFS,
+            Self::Mvs => ZIP_OPSYS_MVS,
+            Self::Vse => ZIP_OPSYS_VSE,
+            Self::AcornRisc => ZIP_OPSYS_ACORN_RISC,
+            Self::Vfat => ZIP_OPSYS_VFAT,
+            Self::AlternateMvs => ZIP_OPSYS_ALTERNATE_MVS,
+            Self::BeOs => ZIP_OPSYS_BEOS,
+            Self::Tandem => ZIP_OPSYS_TANDEM,
+            Self::Os400 => ZIP_OPSYS_OS_400,
+            Self::OsX => ZIP_OPSYS_OS_X,
+            Self::Unknown(opsys) => return *opsys,
+        };
+
+        opsys as zip_uint8_t
+    }
+
+    /// Whether external attributes from this system keep a Unix mode in their
+    /// upper 16 bits.
+    fn has_unix_mode(&self) -> bool {
+        matches!(self, Self::Unix | Self::OsX)
+    }
+
+    /// Whether the low byte of external attributes from this system holds MS-DOS
+    /// attributes.
+    fn has_dos_attributes(&self) -> bool {
+        matches!(self, Self::Dos | Self::WindowsNtfs | Self::Vfat | Self::Os2)
+    }
+}
+
+/// The information libzip reports for an entry through `zip_stat`. Every field
+/// is `None` unless the matching `ZIP_STAT_*` bit is set in `valid`, apart from
+/// `external_attributes`, which `zip_stat` does not cover and is only filled in
+/// when the metadata comes from a [`ZipFile`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryMetadata {
+    pub name: Option<String>,
+    pub index: Option<u64>,
+    pub size: Option<u64>,
+    pub compressed_size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub crc32: Option<u32>,
+    pub compression: Option<CompressionMethod>,
+    pub encryption: Option<EncryptionMethod>,
+    pub external_attributes: Option<(HostOs, u32)>,
+}
+
+impl EntryMetadata {
+    /// # Safety
+    ///
+    /// `stat.name`, when flagged as valid, must point to a nul-terminated string.
+    pub unsafe fn from_stat(stat: &zip_stat_t) -> Self {
+        let valid = |flag: u32| stat.valid & flag as zip_uint64_t != 0;
+
+        let name = if valid(ZIP_STAT_NAME) && !stat.name.is_null() {
+            Some(CStr::from_ptr(stat.name).to_string_lossy().into_owned())
+        } else {
+            None
+        };
+
+        Self {
+            name,
+            index: valid(ZIP_STAT_INDEX).then_some(stat.index),
+            size: valid(ZIP_STAT_SIZE).then_some(stat.size),
+            compressed_size: valid(ZIP_STAT_COMP_SIZE).then_some(stat.comp_size),
+            modified: valid(ZIP_STAT_MTIME).then(|| system_time(stat.mtime)),
+            crc32: valid(ZIP_STAT_CRC).then_some(stat.crc),
+            compression: valid(ZIP_STAT_COMP_METHOD)
+                .then(|| CompressionMethod::from_raw(stat.comp_method)),
+            encryption: valid(ZIP_STAT_ENCRYPTION_METHOD)
+                .then(|| EncryptionMethod::from_raw(stat.encryption_method)),
+            external_attributes: None,
+        }
+    }
+
+    pub fn host_os(&self) -> Option<HostOs> {
+        self.external_attributes.map(|(host_os, _)| host_os)
+    }
+
+    /// The Unix mode, file type bits included, for entries created on Unix.
+    pub fn unix_mode(&self) -> Option<u32> {
+        match self.external_attributes {
+            Some((host_os, attributes)) if host_os.has_unix_mode() && attributes >> 16 != 0 => {
+                Some(attributes >> 16)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+    }
+
+    /// Directories are recognised by a trailing `/` in the name, or by the
+    /// directory bit of the Unix mode or of the MS-DOS attributes.
+    pub fn is_dir(&self) -> bool {
+        if self.name.as_deref().is_some_and(|name| name.ends_with('/')) {
+            return true;
+        }
+        if let Some(mode) = self.unix_mode() {
+            return mode & S_IFMT == S_IFDIR;
+        }
+
+        match self.external_attributes {
+            Some((host_os, attributes)) => {
+                host_os.has_dos_attributes() && attributes & DOS_DIRECTORY != 0
+            }
+            None => false,
+        }
+    }
+}
+
+pub(crate) fn stat_index(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    flags: zip_flags_t,
+) -> ZipResult<EntryMetadata> {
+    unsafe {
+        let mut stat = MaybeUninit::<zip_stat_t>::uninit();
+        zip_stat_init(stat.as_mut_ptr());
+
+        if zip_stat_index(zip_file, index, flags, stat.as_mut_ptr()) != 0 {
+            return Err(ZipError::from_archive(zip_file));
+        }
+
+        Ok(with_attributes(
+            zip_file,
+            index,
+            flags,
+            stat.assume_init_ref(),
+        ))
+    }
+}
+
+pub(crate) fn stat_name(
+    zip_file: *mut zip_t,
+    name: &str,
+    flags: zip_flags_t,
+) -> ZipResult<EntryMetadata> {
+    let c_name = CString::new(name)?;
+
+    unsafe {
+        let mut stat = MaybeUninit::<zip_stat_t>::uninit();
+        zip_stat_init(stat.as_mut_ptr());
+
+        if zip_stat(zip_file, c_name.as_ptr(), flags, stat.as_mut_ptr()) != 0 {
+            return Err(ZipError::from_archive(zip_file));
+        }
+
+        let stat = stat.assume_init_ref();
+        Ok(with_attributes(zip_file, stat.index, flags, stat))
+    }
+}
+
+unsafe fn with_attributes(
+    zip_file: *mut zip_t,
+    index: zip_uint64_t,
+    flags: zip_flags_t,
+    stat: &zip_stat_t,
+) -> EntryMetadata {
+    let mut metadata = EntryMetadata::from_stat(stat);
+    let mut opsys = 0;
+    let mut attributes = 0;
+
+    let flags = flags & ZIP_FL_UNCHANGED as zip_flags_t;
+    if zip_file_get_external_attributes(zip_file, index, flags, &mut opsys, &mut attributes) == 0 {
+        metadata.external_attributes = Some((HostOs::from_raw(opsys), attributes));
+    }
+
+    metadata
+}
+
+pub(crate) fn system_time(time: time_t) -> SystemTime {
+    let secs = i128::from(time);
+    match u64::try_from(secs) {
+        Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+        Err(_) => UNIX_EPOCH - Duration::from_secs((-secs) as u64),
+    }
+}
+
+/// Seconds since the Unix epoch, saturating at the ends of `i64`.
+pub(crate) fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_secs()).unwrap_or(i64::MAX),
+        Err(error) => i64::try_from(error.duration().as_secs()).map_or(i64::MIN, |secs| -secs),
+    }
+}
+
+/// 1980-01-01 00:00:00 and 2107-12-31 23:59:58, the range an MS-DOS
+/// timestamp can hold. libzip stores local time, so these are wall-clock times.
+#[cfg(unix)]
+const DOS_TIME_MIN: (i32, i32, i32, i32, i32, i32) = (1980, 1, 1, 0, 0, 0);
+#[cfg(unix)]
+const DOS_TIME_MAX: (i32, i32, i32, i32, i32, i32) = (2107, 12, 31, 23, 59, 58);
+
+/// The same limits in UTC, pulled in by the largest zone offsets (UTC-12 and
+/// UTC+14) where the local zone is not queried.
+#[cfg(not(unix))]
+const DOS_TIME_MIN: i64 = 315_532_800 + 12 * 3600;
+#[cfg(not(unix))]
+const DOS_TIME_MAX: i64 = 4_354_819_198 - 14 * 3600;
+
+/// Clamps `time` into the MS-DOS range before it is handed to libzip, which
+/// converts it to local time and would otherwise wrap the year of times
+/// outside 1980..=2107.
+pub(crate) fn dos_time_t(time: SystemTime) -> time_t {
+    let (min, max) = dos_range();
+    let secs = unix_seconds(time).clamp(min, max);
+    time_t::try_from(secs).unwrap_or(time_t::MAX)
+}
+
+#[cfg(unix)]
+fn dos_range() -> (i64, i64) {
+    // A `time_t` too narrow for 2107 caps the range at its own maximum.
+    let min = local_time_t(DOS_TIME_MIN).unwrap_or(0);
+    let max = local_time_t(DOS_TIME_MAX).unwrap_or(i64::MAX);
+    (min, max)
+}
+
+#[cfg(not(unix))]
+fn dos_range() -> (i64, i64) {
+    (DOS_TIME_MIN, DOS_TIME_MAX)
+}
+
+#[cfg(unix)]
+fn local_time_t(
+    (year, month, day, hour, minute, second): (i32, i32, i32, i32, i32, i32),
+) -> Option<i64> {
+    let mut tm: tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = year - 1900;
+    tm.tm_mon = month - 1;
+    tm.tm_mday = day;
+    tm.tm_hour = hour;
+    tm.tm_min = minute;
+    tm.tm_sec = second;
+    tm.tm_isdst = -1;
+
+    let time = unsafe { mktime(&mut tm) };
+    // Widened through i128 since `time_t` is 32 bits wide on some targets.
+    (time != -1).then(|| i128::from(time) as i64)
+}
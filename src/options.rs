@@ -0,0 +1,313 @@
+use std::ffi::CString;
+use std::fmt::{Debug, Formatter};
+use std::io::{Read, Seek, Write};
+use std::os::raw::c_int;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::*;
+
+/// Builder for the `zip_open` flags, modelled after [`std::fs::OpenOptions`].
+///
+/// ```no_run
+/// # use libzip_sys::ZipFile;
+/// # use std::path::Path;
+/// let zip = ZipFile::options()
+///     .read_only()
+///     .check_consistency()
+///     .open(Path::new("study.zip"))?;
+/// # Ok::<(), libzip_sys::ZipError>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    flags: c_int,
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the archive if it does not exist (`ZIP_CREATE`).
+    pub fn create(self) -> Self {
+        self.with(ZIP_CREATE)
+    }
+
+    /// Creates the archive, failing with [`ZipError::Exists`] if it already exists
+    /// (`ZIP_CREATE | ZIP_EXCL`).
+    pub fn create_new(self) -> Self {
+        self.with(ZIP_CREATE | ZIP_EXCL)
+    }
+
+    /// Performs additional consistency checks on the archive (`ZIP_CHECKCONS`).
+    pub fn check_consistency(self) -> Self {
+        self.with(ZIP_CHECKCONS)
+    }
+
+    /// Discards the existing contents of the archive (`ZIP_TRUNCATE`).
+    pub fn truncate(self) -> Self {
+        self.with(ZIP_TRUNCATE)
+    }
+
+    /// Opens the archive without allowing any modification (`ZIP_RDONLY`).
+    pub fn read_only(self) -> Self {
+        self.with(ZIP_RDONLY)
+    }
+
+    pub fn flags(&self) -> c_int {
+        self.flags
+    }
+
+    pub fn open(&self, path: &Path) -> ZipResult<ZipFile> {
+        ZipFile::open_with_flags(path, self.flags)
+    }
+
+    pub fn open_reader(&self, reader: impl Read + Seek + Send + 'static) -> ZipResult<ZipFile> {
+        let source = RustSource::new(reader).into_raw()?;
+        ZipFile::open_source(source, self.flags)
+    }
+
+    pub fn open_writable(
+        &self,
+        target: impl Read + Write + Seek + SetLen + Send + 'static,
+    ) -> ZipResult<ZipFile> {
+        let source = RustWritableSource::new(target).into_raw()?;
+        ZipFile::open_source(source, self.flags)
+    }
+
+    fn with(mut self, flag: u32) -> Self {
+        self.flags |= flag as c_int;
+        self
+    }
+}
+
+/// What dropping a [`ZipFile`] that is still open does with its pending changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// Writes the changes with `zip_close`, like [`ZipFile::close`].
+    #[default]
+    Commit,
+    /// Throws the changes away with `zip_discard`, like [`ZipFile::discard`].
+    Discard,
+    /// Discards the changes and panics if there were any, to catch code paths
+    /// that forget to commit. When the thread is already panicking the changes
+    /// are only discarded.
+    PanicIfDirty,
+}
+
+/// Compression applied to an entry when the archive is written. The level is
+/// passed to `zip_set_file_compression`; `0` selects the method's default.
+/// Levels go up to 9, or 22 for zstd; higher ones are rejected with
+/// [`ZipError::Invalid`] when the entry is added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    Default,
+    Store,
+    Deflate(u32),
+    Bzip2(u32),
+    Xz(u32),
+    Zstd(u32),
+}
+
+impl Compression {
+    pub fn method(&self) -> zip_int32_t {
+        match self {
+            Self::Default => ZIP_CM_DEFAULT,
+            Self::Store => ZIP_CM_STORE as zip_int32_t,
+            Self::Deflate(_) => ZIP_CM_DEFLATE as zip_int32_t,
+            Self::Bzip2(_) => ZIP_CM_BZIP2 as zip_int32_t,
+            Self::Xz(_) => ZIP_CM_XZ as zip_int32_t,
+            Self::Zstd(_) => ZIP_CM_ZSTD as zip_int32_t,
+        }
+    }
+
+    pub fn level(&self) -> zip_uint32_t {
+        match self {
+            Self::Default | Self::Store => 0,
+            Self::Deflate(level) | Self::Bzip2(level) | Self::Xz(level) | Self::Zstd(level) => {
+                *level
+            }
+        }
+    }
+
+    /// The highest level `zip_set_file_compression` accepts for this method.
+    fn max_level(&self) -> zip_uint32_t {
+        match self {
+            Self::Default | Self::Store => 0,
+            Self::Deflate(_) | Self::Bzip2(_) | Self::Xz(_) => 9,
+            Self::Zstd(_) => 22,
+        }
+    }
+
+    /// Whether the bundled libzip was built with support for writing this method.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Self::Default => true,
+            _ => unsafe { zip_compression_method_supported(self.method(), 1) != 0 },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncryptionMethod {
+    #[default]
+    None,
+    TraditionalPkware,
+    Aes128,
+    Aes192,
+    Aes256,
+    Unknown(zip_uint16_t),
+}
+
+impl EncryptionMethod {
+    pub fn from_raw(method: zip_uint16_t) -> Self {
+        match method as u32 {
+            ZIP_EM_NONE => Self::None,
+            ZIP_EM_TRAD_PKWARE => Self::TraditionalPkware,
+            ZIP_EM_AES_128 => Self::Aes128,
+            ZIP_EM_AES_192 => Self::Aes192,
+            ZIP_EM_AES_256 => Self::Aes256,
+            _ => Self::Unknown(method),
+        }
+    }
+
+    pub fn raw(&self) -> zip_uint16_t {
+        match self {
+            Self::None => ZIP_EM_NONE as zip_uint16_t,
+            Self::TraditionalPkware => ZIP_EM_TRAD_PKWARE as zip_uint16_t,
+            Self::Aes128 => ZIP_EM_AES_128 as zip_uint16_t,
+            Self::Aes192 => ZIP_EM_AES_192 as zip_uint16_t,
+            Self::Aes256 => ZIP_EM_AES_256 as zip_uint16_t,
+            Self::Unknown(method) => *method,
+        }
+    }
+
+    /// Whether the bundled libzip can encrypt entries with this method.
+    pub fn is_supported(&self) -> bool {
+        unsafe { zip_encryption_method_supported(self.raw(), 1) != 0 }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Password(pub(crate) String);
+
+impl Debug for Password {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"***\"")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EntryOptions {
+    compression: Compression,
+    encryption: Option<(EncryptionMethod, Password)>,
+    unix_mode: Option<u32>,
+    modified: Option<SystemTime>,
+}
+
+impl EntryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn encryption(mut self, method: EncryptionMethod, password: &str) -> Self {
+        self.encryption = Some((method, Password(password.to_string())));
+        self
+    }
+
+    /// Overrides the modification time libzip takes from the source. The same
+    /// MS-DOS resolution and range limits as [`ZipFile::set_entry_mtime`] apply.
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Stores `mode` as the entry's Unix mode, marking it as created on Unix.
+    /// A mode without file type bits is taken to be a regular file.
+    pub fn unix_mode(mut self, mode: u32) -> Self {
+        self.unix_mode = Some(mode);
+        self
+    }
+
+    pub(crate) fn has_unix_mode(&self) -> bool {
+        self.unix_mode.is_some()
+    }
+
+    pub(crate) fn has_modified(&self) -> bool {
+        self.modified.is_some()
+    }
+
+    pub(crate) fn validate(&self) -> ZipResult<()> {
+        if !self.compression.is_supported() {
+            return Err(ZipError::CompressionNotSupported);
+        }
+        if self.compression.level() > self.compression.max_level() {
+            return Err(ZipError::Invalid);
+        }
+
+        if let Some((method, password)) = &self.encryption {
+            if !method.is_supported() {
+                return Err(ZipError::EncryptionNotSupported);
+            }
+            CString::new(password.0.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn apply(&self, zip_file: *mut zip_t, index: zip_uint64_t) -> ZipResult<()> {
+        unsafe {
+            if self.compression != Compression::Default {
+                let result = zip_set_file_compression(
+                    zip_file,
+                    index,
+                    self.compression.method(),
+                    self.compression.level(),
+                );
+                if result != 0 {
+                    return Err(ZipError::from_archive(zip_file));
+                }
+            }
+
+            if let Some((method, password)) = &self.encryption {
+                let password = CString::new(password.0.as_str())?;
+                let result =
+                    zip_file_set_encryption(zip_file, index, method.raw(), password.as_ptr());
+                if result != 0 {
+                    return Err(ZipError::from_archive(zip_file));
+                }
+            }
+
+            if let Some(modified) = self.modified {
+                let result = zip_file_set_mtime(zip_file, index, metadata::dos_time_t(modified), 0);
+                if result != 0 {
+                    return Err(ZipError::from_archive(zip_file));
+                }
+            }
+
+            if let Some(mut mode) = self.unix_mode {
+                if mode & metadata::S_IFMT == 0 {
+                    mode |= metadata::S_IFREG;
+                }
+                let result = zip_file_set_external_attributes(
+                    zip_file,
+                    index,
+                    0,
+                    ZIP_OPSYS_UNIX as zip_uint8_t,
+                    mode << 16,
+                );
+                if result != 0 {
+                    return Err(ZipError::from_archive(zip_file));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
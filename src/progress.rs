@@ -0,0 +1,74 @@
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::*;
+
+type ProgressCallback = Box<dyn FnMut(f64) + Send>;
+
+pub(crate) fn register_progress(
+    zip_file: *mut zip_t,
+    precision: f64,
+    callback: ProgressCallback,
+) -> ZipResult<()> {
+    // Double boxed so the state crossing the FFI boundary is a thin pointer.
+    let state = Box::into_raw(Box::new(callback));
+
+    let result = unsafe {
+        zip_register_progress_callback_with_state(
+            zip_file,
+            precision,
+            Some(progress_callback),
+            Some(free_state::<ProgressCallback>),
+            state as *mut c_void,
+        )
+    };
+
+    if result != 0 {
+        unsafe { drop(Box::from_raw(state)) };
+        return Err(unsafe { ZipError::from_archive(zip_file) });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn register_cancel(zip_file: *mut zip_t, token: Arc<AtomicBool>) -> ZipResult<()> {
+    let state = Box::into_raw(Box::new(token));
+
+    let result = unsafe {
+        zip_register_cancel_callback_with_state(
+            zip_file,
+            Some(cancel_callback),
+            Some(free_state::<Arc<AtomicBool>>),
+            state as *mut c_void,
+        )
+    };
+
+    if result != 0 {
+        unsafe { drop(Box::from_raw(state)) };
+        return Err(unsafe { ZipError::from_archive(zip_file) });
+    }
+
+    Ok(())
+}
+
+unsafe extern "C" fn progress_callback(_zip: *mut zip_t, progress: f64, state: *mut c_void) {
+    let callback = &mut *(state as *mut ProgressCallback);
+
+    // A panic must not unwind into libzip; the progress report is simply lost.
+    let _ = catch_unwind(AssertUnwindSafe(|| callback(progress)));
+}
+
+unsafe extern "C" fn cancel_callback(_zip: *mut zip_t, state: *mut c_void) -> c_int {
+    let token = &*(state as *const Arc<AtomicBool>);
+
+    token.load(Ordering::Relaxed) as c_int
+}
+
+/// Called by libzip when the callback is replaced or the archive is closed or
+/// discarded.
+unsafe extern "C" fn free_state<T>(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut T));
+}
@@ -0,0 +1,581 @@
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::mem::{size_of, MaybeUninit};
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::*;
+
+/// Exposes any Rust [`Read`] + [`Seek`] value to libzip through
+/// `zip_source_function_create`, so archives can be opened from, and entries
+/// read out of, storage that is not a plain file.
+pub struct RustSource<T> {
+    inner: T,
+    error: zip_error_t,
+}
+
+impl<T: Read + Seek + Send + 'static> RustSource<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            error: new_error(),
+        }
+    }
+
+    /// Hands the value over to libzip, which frees it through `ZIP_SOURCE_FREE`.
+    pub fn into_raw(self) -> ZipResult<*mut zip_source_t> {
+        into_raw(self)
+    }
+
+    fn supported() -> &'static [zip_source_cmd_t] {
+        &[
+            zip_source_cmd_ZIP_SOURCE_OPEN,
+            zip_source_cmd_ZIP_SOURCE_READ,
+            zip_source_cmd_ZIP_SOURCE_CLOSE,
+            zip_source_cmd_ZIP_SOURCE_STAT,
+            zip_source_cmd_ZIP_SOURCE_ERROR,
+            zip_source_cmd_ZIP_SOURCE_FREE,
+            zip_source_cmd_ZIP_SOURCE_SEEK,
+            zip_source_cmd_ZIP_SOURCE_TELL,
+            zip_source_cmd_ZIP_SOURCE_SUPPORTS,
+        ]
+    }
+
+    fn check(&mut self, code: u32, result: io::Result<zip_int64_t>) -> zip_int64_t {
+        match result {
+            Ok(value) => value,
+            Err(error) => self.fail_io(code, error),
+        }
+    }
+}
+
+impl<T: Read + Seek + Send + 'static> SourceCallback for RustSource<T> {
+    unsafe fn command(
+        &mut self,
+        data: *mut c_void,
+        len: zip_uint64_t,
+        cmd: zip_source_cmd_t,
+    ) -> zip_int64_t {
+        match cmd {
+            zip_source_cmd_ZIP_SOURCE_OPEN => {
+                let result = self.inner.rewind().map(|_| 0);
+                self.check(ZIP_ER_OPEN, result)
+            }
+            zip_source_cmd_ZIP_SOURCE_READ => {
+                let buf = std::slice::from_raw_parts_mut(data as *mut u8, len as usize);
+                let result = self.inner.read(buf).map(|n| n as zip_int64_t);
+                self.check(ZIP_ER_READ, result)
+            }
+            zip_source_cmd_ZIP_SOURCE_CLOSE => 0,
+            zip_source_cmd_ZIP_SOURCE_STAT => {
+                if (len as usize) < size_of::<zip_stat_t>() {
+                    return self.fail(ZIP_ER_INVAL, 0);
+                }
+
+                let result = stream_len(&mut self.inner);
+                let size = match self.check(ZIP_ER_SEEK, result) {
+                    -1 => return -1,
+                    size => size,
+                };
+
+                let stat = &mut *(data as *mut zip_stat_t);
+                stat.size = size as zip_uint64_t;
+                stat.valid |= ZIP_STAT_SIZE as zip_uint64_t;
+                size_of::<zip_stat_t>() as zip_int64_t
+            }
+            zip_source_cmd_ZIP_SOURCE_SEEK => {
+                let Some(position) = seek_from(data, len) else {
+                    return self.fail(ZIP_ER_INVAL, 0);
+                };
+
+                let result = self.inner.seek(position).map(|_| 0);
+                self.check(ZIP_ER_SEEK, result)
+            }
+            zip_source_cmd_ZIP_SOURCE_TELL => {
+                let result = self.inner.stream_position().map(|n| n as zip_int64_t);
+                self.check(ZIP_ER_TELL, result)
+            }
+            zip_source_cmd_ZIP_SOURCE_ERROR => zip_error_to_data(&self.error, data, len),
+            zip_source_cmd_ZIP_SOURCE_SUPPORTS => command_bitmap(Self::supported()),
+            _ => self.fail(ZIP_ER_OPNOTSUPP, 0),
+        }
+    }
+
+    fn fail(&mut self, code: u32, sys_err: c_int) -> zip_int64_t {
+        unsafe { zip_error_set(&mut self.error, code as c_int, sys_err) };
+        -1
+    }
+}
+
+impl<T> Drop for RustSource<T> {
+    fn drop(&mut self) {
+        unsafe { zip_error_fini(&mut self.error) };
+    }
+}
+
+/// Streams a plain [`Read`] into libzip. The data can only be read once, from
+/// start to end, so the source does not support seeking nor reopening.
+pub(crate) struct ReaderSource<T> {
+    inner: T,
+    error: zip_error_t,
+    opened: bool,
+}
+
+impl<T: Read + Send + 'static> ReaderSource<T> {
+    pub(crate) fn new(inner: T) -> Self {
+        Self {
+            inner,
+            error: new_error(),
+            opened: false,
+        }
+    }
+}
+
+impl<T: Read + Send + 'static> SourceCallback for ReaderSource<T> {
+    unsafe fn command(
+        &mut self,
+        data: *mut c_void,
+        len: zip_uint64_t,
+        cmd: zip_source_cmd_t,
+    ) -> zip_int64_t {
+        match cmd {
+            zip_source_cmd_ZIP_SOURCE_OPEN if self.opened => self.fail(ZIP_ER_OPNOTSUPP, 0),
+            zip_source_cmd_ZIP_SOURCE_OPEN => {
+                self.opened = true;
+                0
+            }
+            zip_source_cmd_ZIP_SOURCE_READ => {
+                let buf = std::slice::from_raw_parts_mut(data as *mut u8, len as usize);
+                match self.inner.read(buf) {
+                    Ok(n) => n as zip_int64_t,
+                    Err(error) => self.fail_io(ZIP_ER_READ, error),
+                }
+            }
+            zip_source_cmd_ZIP_SOURCE_CLOSE => 0,
+            zip_source_cmd_ZIP_SOURCE_STAT => {
+                if (len as usize) < size_of::<zip_stat_t>() {
+                    return self.fail(ZIP_ER_INVAL, 0);
+                }
+
+                size_of::<zip_stat_t>() as zip_int64_t
+            }
+            zip_source_cmd_ZIP_SOURCE_ERROR => zip_error_to_data(&self.error, data, len),
+            zip_source_cmd_ZIP_SOURCE_SUPPORTS => command_bitmap(&[
+                zip_source_cmd_ZIP_SOURCE_OPEN,
+                zip_source_cmd_ZIP_SOURCE_READ,
+                zip_source_cmd_ZIP_SOURCE_CLOSE,
+                zip_source_cmd_ZIP_SOURCE_STAT,
+                zip_source_cmd_ZIP_SOURCE_ERROR,
+                zip_source_cmd_ZIP_SOURCE_FREE,
+                zip_source_cmd_ZIP_SOURCE_SUPPORTS,
+            ]),
+            _ => self.fail(ZIP_ER_OPNOTSUPP, 0),
+        }
+    }
+
+    fn fail(&mut self, code: u32, sys_err: c_int) -> zip_int64_t {
+        unsafe { zip_error_set(&mut self.error, code as c_int, sys_err) };
+        -1
+    }
+}
+
+impl<T> Drop for ReaderSource<T> {
+    fn drop(&mut self) {
+        unsafe { zip_error_fini(&mut self.error) };
+    }
+}
+
+/// Destinations a [`RustWritableSource`] can commit a rewritten archive to.
+///
+/// The new archive may be shorter than the one it replaces, so committing
+/// needs to be able to cut off whatever is left of the old data.
+pub trait SetLen {
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+}
+
+impl SetLen for File {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+}
+
+impl SetLen for Cursor<Vec<u8>> {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.get_mut().resize(len as usize, 0);
+        Ok(())
+    }
+}
+
+/// A [`RustSource`] that also accepts the libzip write protocol, so that
+/// `zip_close` can commit the archive back into any [`Read`] + [`Write`] +
+/// [`Seek`] value.
+///
+/// libzip keeps reading the original archive while it writes the new one, so
+/// the new data is staged, in memory up to 16 MiB and in a temporary file past
+/// that, and only copied into the destination on `ZIP_SOURCE_COMMIT_WRITE`.
+/// When libzip clones the unchanged head of the archive
+/// (`ZIP_SOURCE_BEGIN_WRITE_CLONING`), only the tail is staged and rewritten.
+/// A failed commit is rolled back by libzip and surfaces from
+/// [`ZipFile::close`] as a [`ZipError`], carrying the destination's own I/O
+/// error.
+pub struct RustWritableSource<T> {
+    reader: RustSource<T>,
+    staging: Option<Staging>,
+    offset: u64,
+}
+
+/// Bytes a [`RustWritableSource`] stages in memory before moving them to a
+/// temporary file.
+const SPILL_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+impl<T: Read + Write + Seek + SetLen + Send + 'static> RustWritableSource<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            reader: RustSource::new(inner),
+            staging: None,
+            offset: 0,
+        }
+    }
+
+    /// Hands the value over to libzip, which frees it through `ZIP_SOURCE_FREE`.
+    pub fn into_raw(self) -> ZipResult<*mut zip_source_t> {
+        into_raw(self)
+    }
+
+    fn begin_write(&mut self, offset: u64) -> zip_int64_t {
+        self.staging = Some(Staging::Memory(Cursor::new(Vec::new())));
+        self.offset = offset;
+        0
+    }
+
+    unsafe fn write(&mut self, data: *mut c_void, len: zip_uint64_t) -> zip_int64_t {
+        let Some(staging) = self.staging.as_mut() else {
+            return self.fail(ZIP_ER_INVAL, 0);
+        };
+
+        let buf = std::slice::from_raw_parts(data as *const u8, len as usize);
+        let result = staging.write_all(buf).map(|_| len as zip_int64_t);
+        self.reader.check(ZIP_ER_WRITE, result)
+    }
+
+    unsafe fn seek_write(&mut self, data: *mut c_void, len: zip_uint64_t) -> zip_int64_t {
+        let Some(staging) = self.staging.as_mut() else {
+            return self.fail(ZIP_ER_INVAL, 0);
+        };
+
+        let (position, length) = match staging.position().and_then(|p| Ok((p, staging.len()?))) {
+            Ok((position, length)) => (self.offset + position, self.offset + length),
+            Err(error) => return self.reader.fail_io(ZIP_ER_SEEK, error),
+        };
+        let new_position =
+            zip_source_seek_compute_offset(position, length, data, len, &mut self.reader.error);
+
+        match u64::try_from(new_position) {
+            Ok(new_position) if new_position >= self.offset => {
+                let result = staging.set_position(new_position - self.offset).map(|_| 0);
+                self.reader.check(ZIP_ER_SEEK, result)
+            }
+            Ok(_) => self.fail(ZIP_ER_INVAL, 0),
+            Err(_) => -1,
+        }
+    }
+
+    fn tell_write(&mut self) -> zip_int64_t {
+        let Some(staging) = self.staging.as_mut() else {
+            return self.fail(ZIP_ER_INVAL, 0);
+        };
+
+        let result = staging
+            .position()
+            .map(|position| (self.offset + position) as zip_int64_t);
+        self.reader.check(ZIP_ER_TELL, result)
+    }
+
+    fn commit_write(&mut self) -> zip_int64_t {
+        let Some(mut staging) = self.staging.take() else {
+            return self.fail(ZIP_ER_INVAL, 0);
+        };
+
+        let offset = self.offset;
+        let inner = &mut self.reader.inner;
+        let result = inner
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| staging.copy_to(inner))
+            .and_then(|len| inner.flush().map(|_| len))
+            .and_then(|len| inner.set_len(offset + len))
+            .map(|_| 0);
+
+        self.reader.check(ZIP_ER_WRITE, result)
+    }
+
+    fn remove(&mut self) -> zip_int64_t {
+        let result = self.reader.inner.set_len(0).map(|_| 0);
+        self.reader.check(ZIP_ER_REMOVE, result)
+    }
+}
+
+impl<T: Read + Write + Seek + SetLen + Send + 'static> SourceCallback for RustWritableSource<T> {
+    unsafe fn command(
+        &mut self,
+        data: *mut c_void,
+        len: zip_uint64_t,
+        cmd: zip_source_cmd_t,
+    ) -> zip_int64_t {
+        match cmd {
+            zip_source_cmd_ZIP_SOURCE_BEGIN_WRITE => self.begin_write(0),
+            zip_source_cmd_ZIP_SOURCE_BEGIN_WRITE_CLONING => self.begin_write(len),
+            zip_source_cmd_ZIP_SOURCE_WRITE => self.write(data, len),
+            zip_source_cmd_ZIP_SOURCE_SEEK_WRITE => self.seek_write(data, len),
+            zip_source_cmd_ZIP_SOURCE_TELL_WRITE => self.tell_write(),
+            zip_source_cmd_ZIP_SOURCE_COMMIT_WRITE => self.commit_write(),
+            zip_source_cmd_ZIP_SOURCE_ROLLBACK_WRITE => {
+                self.staging = None;
+                0
+            }
+            zip_source_cmd_ZIP_SOURCE_REMOVE => self.remove(),
+            zip_source_cmd_ZIP_SOURCE_SUPPORTS => {
+                command_bitmap(RustSource::<T>::supported())
+                    | command_bitmap(&[
+                        zip_source_cmd_ZIP_SOURCE_BEGIN_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_BEGIN_WRITE_CLONING,
+                        zip_source_cmd_ZIP_SOURCE_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_SEEK_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_TELL_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_COMMIT_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_ROLLBACK_WRITE,
+                        zip_source_cmd_ZIP_SOURCE_REMOVE,
+                    ])
+            }
+            _ => self.reader.command(data, len, cmd),
+        }
+    }
+
+    fn fail(&mut self, code: u32, sys_err: c_int) -> zip_int64_t {
+        self.reader.fail(code, sys_err)
+    }
+}
+
+/// Where a [`RustWritableSource`] keeps the archive being written.
+enum Staging {
+    Memory(Cursor<Vec<u8>>),
+    File(SpillFile),
+}
+
+impl Staging {
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if let Self::Memory(cursor) = self {
+            if cursor.position() + buf.len() as u64 > SPILL_THRESHOLD {
+                *self = Self::File(SpillFile::new(cursor)?);
+            }
+        }
+
+        match self {
+            Self::Memory(cursor) => cursor.write_all(buf),
+            Self::File(spill) => spill.file.write_all(buf),
+        }
+    }
+
+    fn position(&mut self) -> io::Result<u64> {
+        match self {
+            Self::Memory(cursor) => Ok(cursor.position()),
+            Self::File(spill) => spill.file.stream_position(),
+        }
+    }
+
+    fn set_position(&mut self, position: u64) -> io::Result<()> {
+        match self {
+            Self::Memory(cursor) => cursor.set_position(position),
+            Self::File(spill) => {
+                spill.file.seek(SeekFrom::Start(position))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn len(&self) -> io::Result<u64> {
+        match self {
+            Self::Memory(cursor) => Ok(cursor.get_ref().len() as u64),
+            Self::File(spill) => Ok(spill.file.metadata()?.len()),
+        }
+    }
+
+    /// Writes everything staged into `target` and returns its length.
+    fn copy_to(&mut self, target: &mut impl Write) -> io::Result<u64> {
+        match self {
+            Self::Memory(cursor) => {
+                target.write_all(cursor.get_ref())?;
+                Ok(cursor.get_ref().len() as u64)
+            }
+            Self::File(spill) => {
+                spill.file.rewind()?;
+                io::copy(&mut spill.file, target)
+            }
+        }
+    }
+}
+
+/// A temporary file that is deleted again when dropped.
+struct SpillFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    /// Moves what `cursor` staged so far into a new temporary file, keeping
+    /// the write position.
+    fn new(cursor: &Cursor<Vec<u8>>) -> io::Result<Self> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let name = format!(
+            "libzip-sys-{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let mut spill = Self { file, path };
+        spill.file.write_all(cursor.get_ref())?;
+        spill.file.seek(SeekFrom::Start(cursor.position()))?;
+        Ok(spill)
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+thread_local! {
+    /// The I/O error behind the last failure a Rust source reported to libzip
+    /// on this thread, along with the `ZIP_ER_*` code it was reported as.
+    /// libzip only keeps the raw OS error, which custom errors do not have.
+    static LAST_IO_ERROR: RefCell<Option<(u32, io::Error)>> = const { RefCell::new(None) };
+}
+
+fn stash_io_error(code: u32, error: io::Error) {
+    LAST_IO_ERROR.with(|last| *last.borrow_mut() = Some((code, error)));
+}
+
+/// Takes the stashed I/O error if it was reported as `code`. Sources run on
+/// the thread that calls into libzip, so this finds the error behind a failure
+/// libzip has just returned.
+pub(crate) fn take_io_error(code: c_int) -> Option<io::Error> {
+    LAST_IO_ERROR
+        .with(|last| last.borrow_mut().take())
+        .filter(|(stashed, _)| *stashed as c_int == code)
+        .map(|(_, error)| error)
+}
+
+/// The commands shared by every Rust value handed to `zip_source_function_create`.
+pub(crate) trait SourceCallback: Sized + 'static {
+    /// # Safety
+    ///
+    /// `data` and `len` must describe the argument libzip passes along with `cmd`.
+    unsafe fn command(
+        &mut self,
+        data: *mut c_void,
+        len: zip_uint64_t,
+        cmd: zip_source_cmd_t,
+    ) -> zip_int64_t;
+
+    fn fail(&mut self, code: u32, sys_err: c_int) -> zip_int64_t;
+
+    /// Fails like [`SourceCallback::fail`] and keeps `error` for the
+    /// [`ZipError`] that libzip's failure turns into.
+    fn fail_io(&mut self, code: u32, error: io::Error) -> zip_int64_t {
+        let result = self.fail(code, error.raw_os_error().unwrap_or(0));
+        stash_io_error(code, error);
+        result
+    }
+}
+
+pub(crate) fn into_raw<S: SourceCallback>(source: S) -> ZipResult<*mut zip_source_t> {
+    let userdata = Box::into_raw(Box::new(source));
+
+    unsafe {
+        let mut error = MaybeUninit::<zip_error_t>::uninit();
+        zip_error_init(error.as_mut_ptr());
+
+        let source = zip_source_function_create(
+            Some(source_callback::<S>),
+            userdata as *mut c_void,
+            error.as_mut_ptr(),
+        );
+
+        let result = if source.is_null() {
+            drop(Box::from_raw(userdata));
+            Err(ZipError::from_zip_error(error.as_ptr()))
+        } else {
+            Ok(source)
+        };
+
+        zip_error_fini(error.as_mut_ptr());
+        result
+    }
+}
+
+unsafe extern "C" fn source_callback<S: SourceCallback>(
+    userdata: *mut c_void,
+    data: *mut c_void,
+    len: zip_uint64_t,
+    cmd: zip_source_cmd_t,
+) -> zip_int64_t {
+    let source = userdata as *mut S;
+
+    if cmd == zip_source_cmd_ZIP_SOURCE_FREE {
+        drop(Box::from_raw(source));
+        return 0;
+    }
+
+    match catch_unwind(AssertUnwindSafe(|| (*source).command(data, len, cmd))) {
+        Ok(result) => result,
+        Err(_) => (*source).fail(ZIP_ER_INTERNAL, 0),
+    }
+}
+
+fn new_error() -> zip_error_t {
+    let mut error = MaybeUninit::<zip_error_t>::uninit();
+    unsafe {
+        zip_error_init(error.as_mut_ptr());
+        error.assume_init()
+    }
+}
+
+pub(crate) fn command_bitmap(commands: &[zip_source_cmd_t]) -> zip_int64_t {
+    commands
+        .iter()
+        .fold(0, |bitmap, command| bitmap | (1 << command))
+}
+
+pub(crate) unsafe fn seek_from(data: *mut c_void, len: zip_uint64_t) -> Option<SeekFrom> {
+    if (len as usize) < size_of::<zip_source_args_seek_t>() {
+        return None;
+    }
+
+    let args = &*(data as *const zip_source_args_seek_t);
+    match args.whence {
+        0 => u64::try_from(args.offset).ok().map(SeekFrom::Start),
+        1 => Some(SeekFrom::Current(args.offset)),
+        2 => Some(SeekFrom::End(args.offset)),
+        _ => None,
+    }
+}
+
+fn stream_len(inner: &mut impl Seek) -> io::Result<zip_int64_t> {
+    let position = inner.stream_position()?;
+    let len = inner.seek(SeekFrom::End(0))?;
+    inner.seek(SeekFrom::Start(position))?;
+
+    Ok(len as zip_int64_t)
+}
@@ -0,0 +1,259 @@
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+
+use crate::metadata::{DOS_DIRECTORY, S_IFDIR, S_IFLNK, S_IFREG};
+use crate::*;
+
+/// How [`ZipFile::add_dir_all`] treats symlinks found while walking.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Adds what the link points to. Links that lead back into one of the
+    /// directories being walked are skipped to avoid endless recursion.
+    #[default]
+    Follow,
+    /// Stores the link itself, as an entry holding the target path with a Unix
+    /// symlink mode.
+    Store,
+    Skip,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AddDirOptions {
+    entry: EntryOptions,
+    symlinks: SymlinkPolicy,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl AddDirOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options applied to every file entry that gets added. A Unix mode or
+    /// modification time set here wins over the one read from the file.
+    pub fn entry_options(mut self, entry: EntryOptions) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Only adds files whose path, relative to the walked directory and using `/`
+    /// as separator, matches one of the include patterns. Directories are kept
+    /// when something inside them is added, or when they match themselves.
+    pub fn include(mut self, pattern: &str) -> ZipResult<Self> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Skips files and whole directories whose relative path matches the pattern.
+    pub fn exclude(mut self, pattern: &str) -> ZipResult<Self> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    fn is_excluded(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|pattern| pattern.matches(relative))
+    }
+
+    fn is_included(&self, relative: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(relative))
+    }
+}
+
+struct Walker<'a> {
+    zip: &'a ZipFile,
+    prefix: String,
+    options: &'a AddDirOptions,
+    ancestors: Vec<PathBuf>,
+    // Directories walked into but not written yet: their entry is only added
+    // once something below them is, or when they turn out to be empty.
+    pending: Vec<(String, Metadata)>,
+}
+
+pub(crate) fn add_dir_all(
+    zip: &ZipFile,
+    src_dir: &Path,
+    prefix: &str,
+    options: &AddDirOptions,
+) -> ZipResult<()> {
+    if !fs::metadata(src_dir)?.is_dir() {
+        return Err(ZipError::InvalidPath(src_dir.to_path_buf()));
+    }
+
+    let prefix = prefix.trim_matches('/');
+    let mut walker = Walker {
+        zip,
+        prefix: if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        },
+        options,
+        ancestors: vec![src_dir.canonicalize()?],
+        pending: Vec::new(),
+    };
+
+    walker.walk(src_dir, "")
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, dir: &Path, relative_dir: &str) -> ZipResult<()> {
+        let mut children = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        children.sort_by_key(|child| child.file_name());
+
+        for child in children {
+            let path = child.path();
+            let name = child.file_name();
+            let name = name
+                .to_str()
+                .ok_or_else(|| ZipError::InvalidPath(path.clone()))?;
+            let relative = format!("{}{}", relative_dir, name);
+
+            if self.options.is_excluded(&relative) {
+                continue;
+            }
+
+            let mut metadata = fs::symlink_metadata(&path)?;
+            if metadata.file_type().is_symlink() {
+                match self.options.symlinks {
+                    SymlinkPolicy::Skip => continue,
+                    SymlinkPolicy::Store => {
+                        if self.options.is_included(&relative) {
+                            self.add_symlink(&path, &relative, &metadata)?;
+                        }
+                        continue;
+                    }
+                    SymlinkPolicy::Follow => metadata = fs::metadata(&path)?,
+                }
+            }
+
+            if metadata.is_dir() {
+                let canonical = path.canonicalize()?;
+                if self.ancestors.contains(&canonical) {
+                    continue;
+                }
+
+                let relative = format!("{}/", relative);
+                self.ancestors.push(canonical);
+                self.pending.push((relative.clone(), metadata));
+                self.walk(&path, &relative)?;
+                self.ancestors.pop();
+
+                if let Some((relative, metadata)) = self.pending.pop() {
+                    if self.options.is_included(relative.trim_end_matches('/')) {
+                        self.flush_pending()?;
+                        self.add_directory(&relative, &metadata)?;
+                    }
+                }
+            } else if self.options.is_included(&relative) {
+                self.add_file(&path, &relative, &metadata)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn flush_pending(&mut self) -> ZipResult<()> {
+        for (relative, metadata) in std::mem::take(&mut self.pending) {
+            self.add_directory(&relative, &metadata)?;
+        }
+
+        Ok(())
+    }
+
+    fn add_directory(&mut self, relative: &str, metadata: &Metadata) -> ZipResult<()> {
+        let name = format!("{}{}", self.prefix, relative);
+        let index = match self.zip.index_of(&name, 0)? {
+            Some(index) => index,
+            None => self.zip.add_dir(&name)?,
+        };
+
+        self.set_attributes(
+            index,
+            metadata,
+            S_IFDIR | 0o755,
+            DOS_DIRECTORY,
+            &EntryOptions::new(),
+        )
+    }
+
+    fn add_file(&mut self, path: &Path, relative: &str, metadata: &Metadata) -> ZipResult<()> {
+        self.flush_pending()?;
+
+        let name = format!("{}{}", self.prefix, relative);
+        self.zip
+            .add_file_with_options(path, &name, &self.options.entry)?;
+        let index = self.zip.index_of(&name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        self.set_attributes(index, metadata, S_IFREG | 0o644, 0, &self.options.entry)
+    }
+
+    fn add_symlink(&mut self, path: &Path, relative: &str, metadata: &Metadata) -> ZipResult<()> {
+        self.flush_pending()?;
+
+        let target = fs::read_link(path)?;
+        let target = target
+            .to_str()
+            .ok_or_else(|| ZipError::InvalidPath(path.to_path_buf()))?
+            .replace('\\', "/");
+
+        let name = format!("{}{}", self.prefix, relative);
+        self.zip.add_symlink(&name, &target)?;
+        let index = self.zip.index_of(&name, 0)?.ok_or(ZipError::NoEntry)?;
+
+        self.set_attributes(index, metadata, S_IFLNK | 0o777, 0, &EntryOptions::new())
+    }
+
+    /// Copies mode and mtime from `metadata`, except what `entry` already set.
+    fn set_attributes(
+        &self,
+        index: u64,
+        metadata: &Metadata,
+        default_mode: u32,
+        dos_attributes: u32,
+        entry: &EntryOptions,
+    ) -> ZipResult<()> {
+        if !entry.has_unix_mode() {
+            let mode = unix_mode(metadata).unwrap_or(default_mode);
+            self.zip.set_external_attributes(
+                index,
+                ZIP_OPSYS_UNIX as u8,
+                mode << 16 | dos_attributes,
+            )?;
+        }
+
+        if !entry.has_modified() {
+            if let Ok(modified) = metadata.modified() {
+                self.zip.set_mtime(index, modified)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(metadata: &Metadata) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.mode())
+}
+
+#[cfg(not(unix))]
+fn unix_mode(metadata: &Metadata) -> Option<u32> {
+    // Only the read-only flag maps onto a mode elsewhere; symlinks keep the
+    // default so their type bits survive.
+    if metadata.permissions().readonly() && !metadata.file_type().is_symlink() {
+        let kind = if metadata.is_dir() { S_IFDIR } else { S_IFREG };
+        return Some(kind | 0o555);
+    }
+
+    None
+}
@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use libzip_sys::{AddDirOptions, ZipFile};
+
+struct Scratch(PathBuf);
+
+impl Scratch {
+    /// A tree holding `a.txt`, `b.log`, an `empty/` directory and `sub/` with
+    /// `c.txt` and `d.log`.
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("libzip-sys-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        for file in ["a.txt", "b.log", "sub/c.txt", "sub/d.log"] {
+            fs::write(root.join(file), file).unwrap();
+        }
+        Self(root)
+    }
+
+    fn root(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn names(zip: &ZipFile) -> Vec<String> {
+    let mut names: Vec<_> = zip
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|entry| entry.name())
+        .collect();
+    names.sort();
+    names
+}
+
+fn add_dir(scratch: &Scratch, options: &AddDirOptions) -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.add_dir_all(scratch.root(), "root", options).unwrap();
+    zip
+}
+
+#[test]
+fn adds_everything_by_default() {
+    let scratch = Scratch::new("add-all");
+    let zip = add_dir(&scratch, &AddDirOptions::new());
+
+    assert_eq!(
+        names(&zip),
+        [
+            "root/a.txt",
+            "root/b.log",
+            "root/empty/",
+            "root/sub/",
+            "root/sub/c.txt",
+            "root/sub/d.log",
+        ]
+    );
+}
+
+#[test]
+fn include_keeps_only_matching_files_and_their_directories() {
+    let scratch = Scratch::new("add-include");
+    let options = AddDirOptions::new().include("*.txt").unwrap();
+    let zip = add_dir(&scratch, &options);
+
+    // `empty/` has nothing included below it and does not match itself.
+    assert_eq!(names(&zip), ["root/a.txt", "root/sub/", "root/sub/c.txt"]);
+}
+
+#[test]
+fn exclude_skips_files_and_whole_directories() {
+    let scratch = Scratch::new("add-exclude");
+    let options = AddDirOptions::new()
+        .exclude("*.log")
+        .unwrap()
+        .exclude("sub")
+        .unwrap();
+    let zip = add_dir(&scratch, &options);
+
+    assert_eq!(names(&zip), ["root/a.txt", "root/empty/"]);
+}
+
+#[test]
+fn empty_directory_is_added_when_it_matches_itself() {
+    let scratch = Scratch::new("add-empty");
+    let options = AddDirOptions::new().include("empty").unwrap();
+    let zip = add_dir(&scratch, &options);
+
+    assert_eq!(names(&zip), ["root/empty/"]);
+}
+
+#[cfg(unix)]
+mod symlinks {
+    use std::io::Read;
+    use std::os::unix::fs::symlink;
+
+    use libzip_sys::SymlinkPolicy;
+
+    use super::*;
+
+    /// The scratch tree plus `link.txt` pointing at `a.txt` and `loop`
+    /// pointing back at the walked directory itself.
+    fn scratch(name: &str) -> Scratch {
+        let scratch = Scratch::new(name);
+        symlink("a.txt", scratch.root().join("link.txt")).unwrap();
+        symlink(".", scratch.root().join("loop")).unwrap();
+        scratch
+    }
+
+    fn add_with(scratch: &Scratch, symlinks: SymlinkPolicy) -> ZipFile {
+        add_dir(scratch, &AddDirOptions::new().symlinks(symlinks))
+    }
+
+    #[test]
+    fn store_keeps_links_as_links() {
+        let scratch = scratch("add-store");
+        let zip = add_with(&scratch, SymlinkPolicy::Store);
+
+        assert_eq!(
+            zip.symlink_target("root/link.txt").unwrap().as_deref(),
+            Some("a.txt")
+        );
+        assert_eq!(
+            zip.symlink_target("root/loop").unwrap().as_deref(),
+            Some(".")
+        );
+    }
+
+    #[test]
+    fn skip_leaves_links_out() {
+        let scratch = scratch("add-skip");
+        let zip = add_with(&scratch, SymlinkPolicy::Skip);
+
+        assert_eq!(zip.index_of("root/link.txt", 0).unwrap(), None);
+        assert_eq!(zip.index_of("root/loop", 0).unwrap(), None);
+        assert_eq!(zip.index_of("root/loop/", 0).unwrap(), None);
+    }
+
+    #[test]
+    fn follow_adds_targets_and_skips_loops() {
+        let scratch = scratch("add-follow");
+        let zip = add_with(&scratch, SymlinkPolicy::Follow);
+
+        assert!(!zip.metadata("root/link.txt").unwrap().is_symlink());
+        let mut data = Vec::new();
+        zip.get_entry("root/link.txt", true)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"a.txt");
+
+        assert!(names(&zip).iter().all(|name| !name.contains("loop")));
+    }
+}
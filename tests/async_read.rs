@@ -0,0 +1,87 @@
+#![cfg(feature = "tokio")]
+
+use libzip_sys::{Compression, EntryOptions, ZipFile};
+use tokio::io::AsyncReadExt;
+
+const ENTRY_SIZE: usize = 5 * 1024 * 1024 + 123;
+
+fn sample_data() -> Vec<u8> {
+    // Cheap xorshift noise: compresses poorly, so deflate has real work to do.
+    let mut state = 0x2545_f491_u32;
+    (0..ENTRY_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn archive_with(data: &[u8], compression: Compression) -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    let options = EntryOptions::new().compression(compression);
+    zip.add_bytes(data.to_vec(), "data.bin", &options).unwrap();
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+async fn read_entry(zip: &ZipFile) -> Vec<u8> {
+    let mut entry = zip.get_entry("data.bin", true).unwrap();
+    let mut read = Vec::new();
+    entry.read_to_end(&mut read).await.unwrap();
+    read
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_large_deflated_entry_exactly() {
+    let data = sample_data();
+    let zip = archive_with(&data, Compression::Deflate(0));
+
+    assert_eq!(read_entry(&zip).await, data);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reads_large_stored_entry_exactly() {
+    let data = sample_data();
+    let zip = archive_with(&data, Compression::Store);
+
+    assert_eq!(read_entry(&zip).await, data);
+}
+
+#[tokio::test]
+async fn reads_in_small_chunks_on_current_thread_runtime() {
+    let data = sample_data();
+    let zip = archive_with(&data, Compression::Deflate(0));
+    let mut entry = zip.get_entry("data.bin", true).unwrap();
+
+    let mut read = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let len = entry.read(&mut chunk).await.unwrap();
+        if len == 0 {
+            break;
+        }
+        read.extend_from_slice(&chunk[..len]);
+    }
+
+    assert_eq!(read, data);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn archive_stays_usable_between_async_reads() {
+    let data = sample_data();
+    let zip = archive_with(&data, Compression::Deflate(0));
+    let mut entry = zip.get_entry("data.bin", true).unwrap();
+
+    let mut read = vec![0u8; 1024];
+    entry.read_exact(&mut read).await.unwrap();
+    assert_eq!(zip.len(), 1);
+    assert_eq!(
+        zip.metadata("data.bin").unwrap().size,
+        Some(ENTRY_SIZE as u64)
+    );
+
+    entry.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, data);
+}
@@ -0,0 +1,72 @@
+use std::fs;
+use std::io::Read;
+
+use libzip_sys::{Compression, EntryOptions, ZipError, ZipFile};
+
+fn read_entry(zip: &ZipFile, name: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    zip.get_entry(name, true)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+#[test]
+fn add_buffer_outlives_the_callers_buffer() {
+    let path = std::env::temp_dir().join(format!("libzip-sys-{}-buffer.zip", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut zip = ZipFile::open(&path, true).unwrap();
+    {
+        let mut data = b"written after the buffer is gone".to_vec();
+        zip.add_buffer(&data, "buffer.txt").unwrap();
+        // Scribble over and free the buffer before libzip reads the entry.
+        data.fill(0);
+    }
+    zip.close().unwrap();
+
+    let zip = ZipFile::open(&path, false).unwrap();
+    assert_eq!(
+        read_entry(&zip, "buffer.txt"),
+        b"written after the buffer is gone"
+    );
+    drop(zip);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn add_bytes_round_trips_in_memory() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    let data: Vec<u8> = (0..=255).cycle().take(64 * 1024).collect();
+    zip.add_bytes(data.clone(), "bytes.bin", &EntryOptions::new())
+        .unwrap();
+
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+    assert_eq!(read_entry(&zip, "bytes.bin"), data);
+}
+
+#[test]
+fn add_bytes_rejects_out_of_range_levels() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    for compression in [Compression::Deflate(10), Compression::Zstd(23)] {
+        let options = EntryOptions::new().compression(compression);
+        let result = zip.add_bytes(b"data".to_vec(), "data.txt", &options);
+
+        assert!(matches!(result, Err(ZipError::Invalid)), "{result:?}");
+    }
+    assert!(zip.is_empty());
+}
+
+#[test]
+fn add_bytes_overwrites_an_entry_added_earlier() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.add_bytes(b"first".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+    zip.add_bytes(b"second".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+    assert_eq!(zip.len(), 1);
+    assert_eq!(read_entry(&zip, "data.txt"), b"second");
+}
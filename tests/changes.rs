@@ -0,0 +1,89 @@
+use std::io::Cursor;
+
+use libzip_sys::{EntryOptions, PendingChange, RustSource, ZipFile};
+
+/// `a.txt` to `d.txt`, read back from the written bytes so every entry is an
+/// original one.
+fn archive() -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        zip.add_bytes(name.as_bytes().to_vec(), name, &EntryOptions::new())
+            .unwrap();
+    }
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+/// Renames `a.txt`, replaces `b.txt`, deletes `c.txt` and adds `e.txt`.
+fn edit(zip: &ZipFile) {
+    zip.rename_entry("a.txt", "renamed.txt").unwrap();
+    zip.replace_entry("b.txt", RustSource::new(Cursor::new(b"new data".to_vec())))
+        .unwrap();
+    zip.delete_file("c.txt").unwrap();
+    zip.add_bytes(b"e.txt".to_vec(), "e.txt", &EntryOptions::new())
+        .unwrap();
+}
+
+#[test]
+fn pending_changes_lists_every_kind_of_change() {
+    let zip = archive();
+    assert!(zip.pending_changes().unwrap().is_empty());
+    assert!(!zip.is_modified().unwrap());
+
+    edit(&zip);
+    assert_eq!(
+        zip.pending_changes().unwrap(),
+        [
+            PendingChange::Renamed {
+                index: 0,
+                from: "a.txt".to_string(),
+                to: "renamed.txt".to_string(),
+            },
+            PendingChange::Replaced {
+                index: 1,
+                name: "b.txt".to_string(),
+            },
+            PendingChange::Deleted {
+                index: 2,
+                name: "c.txt".to_string(),
+            },
+            PendingChange::Added {
+                index: 4,
+                name: "e.txt".to_string(),
+            },
+        ]
+    );
+    assert!(zip.is_modified().unwrap());
+}
+
+#[test]
+fn renamed_entry_is_reverted_by_its_original_name() {
+    let zip = archive();
+    zip.rename_entry("a.txt", "renamed.txt").unwrap();
+
+    zip.revert_entry("a.txt").unwrap();
+    assert_eq!(zip.index_of("a.txt", 0).unwrap(), Some(0));
+    assert_eq!(zip.index_of("renamed.txt", 0).unwrap(), None);
+    assert!(zip.pending_changes().unwrap().is_empty());
+}
+
+#[test]
+fn deleted_entry_is_reverted() {
+    let zip = archive();
+    zip.delete_file("c.txt").unwrap();
+
+    zip.revert_entry("c.txt").unwrap();
+    assert_eq!(zip.index_of("c.txt", 0).unwrap(), Some(2));
+    assert!(!zip.is_modified().unwrap());
+}
+
+#[test]
+fn revert_all_drops_every_change() {
+    let zip = archive();
+    edit(&zip);
+
+    zip.revert_all().unwrap();
+    assert!(zip.pending_changes().unwrap().is_empty());
+    assert!(!zip.is_modified().unwrap());
+    assert_eq!(zip.index_of("e.txt", 0).unwrap(), None);
+}
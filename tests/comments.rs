@@ -0,0 +1,62 @@
+use libzip_sys::{CommentEncoding, EntryOptions, ZipFile};
+
+/// Writes `data.txt` with `comment` as its entry comment and reopens the
+/// archive from the written bytes.
+fn archive_with_entry_comment(comment: &[u8], encoding: CommentEncoding) -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.add_bytes(b"data".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+    zip.set_entry_comment("data.txt", comment, encoding)
+        .unwrap();
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn archive_comment_round_trips_utf8() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.set_comment("Grüße ✓").unwrap();
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+
+    assert_eq!(zip.comment().unwrap(), "Grüße ✓");
+    assert_eq!(zip.raw_comment().unwrap(), "Grüße ✓".as_bytes());
+}
+
+#[test]
+fn archive_comment_is_empty_when_missing() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.add_bytes(b"data".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+
+    assert_eq!(zip.comment().unwrap(), "");
+    assert!(zip.raw_comment().unwrap().is_empty());
+}
+
+#[test]
+fn entry_comment_round_trips_utf8() {
+    let zip = archive_with_entry_comment("naïve ✓".as_bytes(), CommentEncoding::Utf8);
+    let entry = zip.get_entry("data.txt", false).unwrap();
+
+    assert_eq!(entry.comment().unwrap(), "naïve ✓");
+    assert_eq!(entry.raw_comment().unwrap(), "naïve ✓".as_bytes());
+}
+
+#[test]
+fn entry_comment_converts_cp437() {
+    // 0x82 is `é` and 0xE1 is `ß` in CP437.
+    let zip = archive_with_entry_comment(b"caf\x82 \xe1", CommentEncoding::Cp437);
+    let entry = zip.get_entry("data.txt", false).unwrap();
+
+    assert_eq!(entry.comment().unwrap(), "café ß");
+    assert_eq!(entry.raw_comment().unwrap(), b"caf\x82 \xe1");
+}
+
+#[test]
+fn entry_comment_keeps_raw_bytes() {
+    let raw = [0x00, 0xff, 0x80, b'a'];
+    let zip = archive_with_entry_comment(&raw, CommentEncoding::Cp437);
+    let entry = zip.get_entry("data.txt", true).unwrap();
+
+    assert_eq!(entry.raw_comment().unwrap(), raw);
+}
@@ -0,0 +1,46 @@
+use std::io::Read;
+
+use libzip_sys::{EncryptionMethod, EntryOptions, ZipError, ZipFile};
+
+fn encrypted_archive() -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    let options = EntryOptions::new().encryption(EncryptionMethod::Aes256, "secret");
+    zip.add_bytes(b"hidden".to_vec(), "secret.txt", &options)
+        .unwrap();
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let zip = encrypted_archive();
+
+    let result = zip.get_entry_with_password("secret.txt", "guess");
+    assert!(matches!(result, Err(ZipError::WrongPassword)), "{result:?}");
+}
+
+#[test]
+fn opening_without_a_password_needs_one() {
+    let zip = encrypted_archive();
+    let index = zip.index_of("secret.txt", 0).unwrap().unwrap();
+
+    let result = zip.entry_by_index(index, true);
+    assert!(matches!(result, Err(ZipError::NoPassword)), "{result:?}");
+}
+
+#[test]
+fn default_password_opens_entries() {
+    let zip = encrypted_archive();
+    assert!(zip.get_entry("secret.txt", true).is_none());
+
+    zip.set_default_password(Some("secret")).unwrap();
+    let mut data = Vec::new();
+    zip.get_entry("secret.txt", true)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    assert_eq!(data, b"hidden");
+
+    let metadata = zip.metadata("secret.txt").unwrap();
+    assert_eq!(metadata.encryption, Some(EncryptionMethod::Aes256));
+}
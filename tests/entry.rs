@@ -0,0 +1,49 @@
+use libzip_sys::{EntryOptions, ZipError, ZipFile};
+
+fn archive() -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    zip.add_bytes(b"data".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn entry_outliving_its_archive_reports_not_open() {
+    let zip = archive();
+    let entry = zip.get_entry("data.txt", false).unwrap();
+    assert_eq!(entry.metadata().unwrap().size, Some(4));
+    drop(zip);
+
+    assert!(matches!(entry.metadata(), Err(ZipError::NotOpen)));
+    assert!(matches!(entry.comment(), Err(ZipError::NotOpen)));
+}
+
+#[test]
+fn entry_of_a_discarded_archive_reports_not_open() {
+    let mut zip = archive();
+    let entry = zip.get_entry("data.txt", false).unwrap();
+    zip.discard();
+
+    assert!(matches!(entry.metadata(), Err(ZipError::NotOpen)));
+    assert!(matches!(entry.raw_comment(), Err(ZipError::NotOpen)));
+}
+
+#[test]
+fn entries_skip_slots_deleted_this_session() {
+    let zip = ZipFile::new_in_memory().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        zip.add_bytes(name.as_bytes().to_vec(), name, &EntryOptions::new())
+            .unwrap();
+    }
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+    zip.delete_file("b.txt").unwrap();
+
+    let names: Vec<String> = zip
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|entry| entry.name())
+        .collect();
+    assert_eq!(names, ["a.txt", "c.txt"]);
+}
@@ -0,0 +1,37 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use libzip_sys::{ExtendedTimestamp, ExtraFieldData, ExtraFieldLocation};
+
+fn round_trip(field: &ExtendedTimestamp) -> ExtendedTimestamp {
+    ExtendedTimestamp::parse(&field.encode(ExtraFieldLocation::Local)).unwrap()
+}
+
+#[test]
+fn extended_timestamp_round_trips() {
+    let field = ExtendedTimestamp {
+        modified: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+        accessed: None,
+        created: Some(UNIX_EPOCH - Duration::from_secs(86_400)),
+    };
+
+    assert_eq!(round_trip(&field), field);
+}
+
+#[test]
+fn extended_timestamp_saturates_out_of_range_times() {
+    let field = ExtendedTimestamp {
+        modified: Some(UNIX_EPOCH + Duration::from_secs(1 << 32)),
+        accessed: Some(UNIX_EPOCH - Duration::from_secs(1 << 32)),
+        created: None,
+    };
+
+    let parsed = round_trip(&field);
+    assert_eq!(
+        parsed.modified,
+        Some(UNIX_EPOCH + Duration::from_secs(i32::MAX as u64))
+    );
+    assert_eq!(
+        parsed.accessed,
+        Some(UNIX_EPOCH - Duration::from_secs(1 << 31))
+    );
+}
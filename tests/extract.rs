@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use libzip_sys::{EntryOptions, ExtractOptions, ZipError, ZipFile};
+
+/// A scratch directory whose `out` subdirectory is the extraction root, so an
+/// escaping entry lands next to it where the test can look for it.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("libzip-sys-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self(root)
+    }
+
+    fn root(&self) -> &Path {
+        &self.0
+    }
+
+    fn dest(&self) -> PathBuf {
+        self.0.join("out")
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+enum Entry<'a> {
+    File(&'a str, &'a [u8]),
+    Symlink(&'a str, &'a str),
+}
+
+fn archive(entries: &[Entry]) -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    for entry in entries {
+        match *entry {
+            Entry::File(name, data) => zip
+                .add_bytes(data.to_vec(), name, &EntryOptions::new())
+                .unwrap(),
+            Entry::Symlink(name, target) => zip.add_symlink(name, target).unwrap(),
+        }
+    }
+
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+fn extract(scratch: &Scratch, entries: &[Entry]) -> Result<(), ZipError> {
+    archive(entries).extract_all(&scratch.dest(), &ExtractOptions::new())
+}
+
+#[test]
+fn extracts_nested_files() {
+    let scratch = Scratch::new("nested");
+    extract(
+        &scratch,
+        &[
+            Entry::File("a/b/c.txt", b"nested"),
+            Entry::File("top.txt", b"top"),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        fs::read(scratch.dest().join("a/b/c.txt")).unwrap(),
+        b"nested"
+    );
+    assert_eq!(fs::read(scratch.dest().join("top.txt")).unwrap(), b"top");
+}
+
+#[test]
+fn skips_entries_deleted_before_extraction() {
+    let scratch = Scratch::new("deleted");
+    let zip = archive(&[
+        Entry::File("a.txt", b"a"),
+        Entry::File("b.txt", b"b"),
+        Entry::File("c.txt", b"c"),
+    ]);
+    zip.delete_file("b.txt").unwrap();
+
+    assert_eq!(zip.iter().count(), 2);
+    zip.extract_all(&scratch.dest(), &ExtractOptions::new())
+        .unwrap();
+
+    assert_eq!(fs::read(scratch.dest().join("a.txt")).unwrap(), b"a");
+    assert!(!scratch.dest().join("b.txt").exists());
+    assert_eq!(fs::read(scratch.dest().join("c.txt")).unwrap(), b"c");
+}
+
+#[test]
+fn rejects_traversal_names() {
+    for name in [
+        "../evil.txt",
+        "a/../../evil.txt",
+        "..\\evil.txt",
+        "/evil.txt",
+        "\\evil.txt",
+        "C:/evil.txt",
+        "C:evil.txt",
+    ] {
+        let scratch = Scratch::new("traversal");
+        let result = extract(&scratch, &[Entry::File(name, b"evil")]);
+
+        assert!(
+            matches!(result, Err(ZipError::UnsafeEntryName(_))),
+            "{name}: {result:?}"
+        );
+        assert!(!scratch.root().join("evil.txt").exists(), "{name}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn rejects_symlink_target_outside_dest() {
+    let scratch = Scratch::new("link-outside");
+    let result = extract(&scratch, &[Entry::Symlink("link", "../outside")]);
+
+    assert!(
+        matches!(result, Err(ZipError::UnsafeEntryName(_))),
+        "{result:?}"
+    );
+    assert!(fs::symlink_metadata(scratch.dest().join("link")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn rejects_chained_symlinks() {
+    let scratch = Scratch::new("link-chain");
+    let result = extract(
+        &scratch,
+        &[
+            Entry::Symlink("b", "."),
+            Entry::Symlink("a", "b/.."),
+            Entry::File("a/evil.txt", b"evil"),
+        ],
+    );
+
+    assert!(result.is_err());
+    assert!(!scratch.root().join("evil.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn rejects_chained_symlinks_in_any_order() {
+    let scratch = Scratch::new("link-chain-reversed");
+    let result = extract(
+        &scratch,
+        &[Entry::Symlink("a", "b/.."), Entry::Symlink("b", ".")],
+    );
+
+    assert!(
+        matches!(result, Err(ZipError::UnsafeEntryName(_))),
+        "{result:?}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn does_not_write_through_existing_symlink() {
+    let scratch = Scratch::new("existing-link");
+    fs::create_dir_all(scratch.dest()).unwrap();
+    std::os::unix::fs::symlink(scratch.root(), scratch.dest().join("a")).unwrap();
+
+    let result = extract(&scratch, &[Entry::File("a/evil.txt", b"evil")]);
+
+    assert!(
+        matches!(result, Err(ZipError::UnsafeEntryName(_))),
+        "{result:?}"
+    );
+    assert!(!scratch.root().join("evil.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn replaces_existing_symlink_file() {
+    let scratch = Scratch::new("existing-file-link");
+    fs::create_dir_all(scratch.dest()).unwrap();
+    fs::write(scratch.root().join("victim.txt"), b"safe").unwrap();
+    std::os::unix::fs::symlink(
+        scratch.root().join("victim.txt"),
+        scratch.dest().join("a.txt"),
+    )
+    .unwrap();
+
+    extract(&scratch, &[Entry::File("a.txt", b"data")]).unwrap();
+
+    assert_eq!(
+        fs::read(scratch.root().join("victim.txt")).unwrap(),
+        b"safe"
+    );
+    assert_eq!(fs::read(scratch.dest().join("a.txt")).unwrap(), b"data");
+}
+
+#[cfg(unix)]
+#[test]
+fn creates_links_inside_dest() {
+    let scratch = Scratch::new("link-inside");
+    extract(
+        &scratch,
+        &[
+            Entry::Symlink("dir/link", "../data.txt"),
+            Entry::File("data.txt", b"data"),
+        ],
+    )
+    .unwrap();
+
+    let link = scratch.dest().join("dir/link");
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read(link).unwrap(), b"data");
+}
+
+#[test]
+fn refuses_symlinks_when_disabled() {
+    let scratch = Scratch::new("link-refused");
+    let options = ExtractOptions::new().allow_symlinks(false);
+    let result =
+        archive(&[Entry::Symlink("link", "target")]).extract_all(&scratch.dest(), &options);
+
+    assert!(
+        matches!(result, Err(ZipError::SymlinkRefused(_))),
+        "{result:?}"
+    );
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libzip_sys::{EntryOptions, ZipFile};
+
+/// 1980-01-01 00:00:00 and 2107-12-31 23:59:58 UTC, the ends of the MS-DOS
+/// range. The range is applied in local time, which is at most 14 hours away.
+const DOS_MIN: u64 = 315_532_800;
+const DOS_MAX: u64 = 4_354_819_198;
+const MAX_UTC_OFFSET: u64 = 14 * 3600;
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+/// The mtime read back for an entry stored with `modified`, set once through
+/// [`EntryOptions::modified`] and once through [`ZipFile::set_entry_mtime`].
+fn round_trip(modified: SystemTime) -> [u64; 2] {
+    let zip = ZipFile::new_in_memory().unwrap();
+    let options = EntryOptions::new().modified(modified);
+    zip.add_bytes(b"option".to_vec(), "option.txt", &options)
+        .unwrap();
+    zip.add_bytes(b"setter".to_vec(), "setter.txt", &EntryOptions::new())
+        .unwrap();
+    zip.set_entry_mtime("setter.txt", modified).unwrap();
+
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+    ["option.txt", "setter.txt"].map(|name| secs(zip.metadata(name).unwrap().modified.unwrap()))
+}
+
+#[test]
+fn mtime_before_1980_is_clamped() {
+    for read in round_trip(at(0)) {
+        assert!(read.abs_diff(DOS_MIN) <= MAX_UTC_OFFSET, "{read}");
+    }
+}
+
+#[test]
+fn mtime_after_2107_is_clamped() {
+    // 2200-01-01 00:00:00 UTC.
+    for read in round_trip(at(7_258_118_400)) {
+        assert!(read.abs_diff(DOS_MAX) <= MAX_UTC_OFFSET, "{read}");
+    }
+}
+
+#[test]
+fn odd_seconds_are_rounded_down() {
+    assert_eq!(round_trip(at(1_700_000_001)), [1_700_000_000; 2]);
+}
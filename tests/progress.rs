@@ -0,0 +1,56 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use libzip_sys::{EntryOptions, ZipError, ZipFile};
+
+#[test]
+fn cancelled_close_leaves_the_archive_unchanged() {
+    let path = std::env::temp_dir().join(format!("libzip-sys-{}-cancel.zip", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut zip = ZipFile::open(&path, true).unwrap();
+    zip.add_bytes(b"original".to_vec(), "original.txt", &EntryOptions::new())
+        .unwrap();
+    zip.close().unwrap();
+    let before = fs::read(&path).unwrap();
+
+    let mut zip = ZipFile::open(&path, false).unwrap();
+    zip.add_bytes(b"added".to_vec(), "added.txt", &EntryOptions::new())
+        .unwrap();
+    let token = Arc::new(AtomicBool::new(false));
+    zip.set_cancel_token(token.clone()).unwrap();
+    token.store(true, Ordering::SeqCst);
+
+    assert!(matches!(zip.close(), Err(ZipError::Cancelled)));
+    assert_eq!(fs::read(&path).unwrap(), before);
+
+    // Still open with its changes, so the commit can be retried.
+    token.store(false, Ordering::SeqCst);
+    zip.close().unwrap();
+    let zip = ZipFile::open(&path, false).unwrap();
+    assert!(zip.get_entry("added.txt", false).is_some());
+
+    drop(zip);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn progress_ends_at_one() {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let mut zip = ZipFile::new_in_memory().unwrap();
+    let data: Vec<u8> = (0..=255).cycle().take(256 * 1024).collect();
+    zip.add_bytes(data, "data.bin", &EntryOptions::new())
+        .unwrap();
+
+    let sink = reports.clone();
+    zip.on_progress(0.1, move |fraction| sink.lock().unwrap().push(fraction))
+        .unwrap();
+    zip.close().unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.last(), Some(&1.0), "{reports:?}");
+    assert!(
+        reports.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{reports:?}"
+    );
+}
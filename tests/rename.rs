@@ -0,0 +1,81 @@
+use std::io::{Cursor, Read};
+
+use libzip_sys::{
+    CommentEncoding, EntryOptions, ExtraFieldLocation, RustSource, ZipError, ZipFile,
+};
+
+const EXTRA_ID: u16 = 0xcafe;
+
+/// Reopens `zip` from the bytes it writes when closed.
+fn reopen(zip: ZipFile) -> ZipFile {
+    ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap()
+}
+
+/// `a.txt`, `b.txt` and `c.txt`, with a comment and an extra field on `b.txt`.
+fn archive() -> ZipFile {
+    let zip = ZipFile::new_in_memory().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        zip.add_bytes(name.as_bytes().to_vec(), name, &EntryOptions::new())
+            .unwrap();
+    }
+    zip.set_entry_comment("b.txt", "kept", CommentEncoding::Utf8)
+        .unwrap();
+    zip.set_extra_field("b.txt", EXTRA_ID, b"extra", ExtraFieldLocation::Central)
+        .unwrap();
+
+    reopen(zip)
+}
+
+fn read_entry(zip: &ZipFile, name: &str) -> Vec<u8> {
+    let mut data = Vec::new();
+    zip.get_entry(name, true)
+        .unwrap()
+        .read_to_end(&mut data)
+        .unwrap();
+    data
+}
+
+fn assert_kept(zip: &ZipFile, name: &str) {
+    assert_eq!(zip.index_of(name, 0).unwrap(), Some(1));
+    assert_eq!(
+        zip.get_entry(name, false).unwrap().comment().unwrap(),
+        "kept"
+    );
+    assert_eq!(
+        zip.extra_field(name, EXTRA_ID, ExtraFieldLocation::Central)
+            .unwrap(),
+        Some(b"extra".to_vec())
+    );
+}
+
+#[test]
+fn rename_onto_an_existing_name_fails() {
+    let zip = archive();
+
+    let result = zip.rename_entry("a.txt", "c.txt");
+    assert!(matches!(result, Err(ZipError::Exists)), "{result:?}");
+    assert_eq!(zip.index_of("a.txt", 0).unwrap(), Some(0));
+    assert_eq!(read_entry(&zip, "c.txt"), b"c.txt");
+}
+
+#[test]
+fn rename_keeps_index_comment_and_extra_fields() {
+    let zip = archive();
+    zip.rename_entry("b.txt", "renamed.txt").unwrap();
+
+    let zip = reopen(zip);
+    assert_eq!(zip.index_of("b.txt", 0).unwrap(), None);
+    assert_kept(&zip, "renamed.txt");
+    assert_eq!(read_entry(&zip, "renamed.txt"), b"b.txt");
+}
+
+#[test]
+fn replace_keeps_index_comment_and_extra_fields() {
+    let zip = archive();
+    zip.replace_entry("b.txt", RustSource::new(Cursor::new(b"replaced".to_vec())))
+        .unwrap();
+
+    let zip = reopen(zip);
+    assert_kept(&zip, "b.txt");
+    assert_eq!(read_entry(&zip, "b.txt"), b"replaced");
+}
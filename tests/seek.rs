@@ -0,0 +1,26 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use libzip_sys::{Compression, EncryptionMethod, EntryOptions, ZipFile};
+
+#[test]
+fn emulated_backward_seek_reopens_encrypted_entry() {
+    let data: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+    let zip = ZipFile::new_in_memory().unwrap();
+    let options = EntryOptions::new()
+        .compression(Compression::Deflate(0))
+        .encryption(EncryptionMethod::Aes256, "secret");
+    zip.add_bytes(data.clone(), "secret.bin", &options).unwrap();
+    let zip = ZipFile::from_bytes(zip.into_bytes().unwrap()).unwrap();
+
+    let mut entry = zip.get_entry_with_password("secret.bin", "secret").unwrap();
+    entry.emulate_seek(true);
+
+    let mut read = Vec::new();
+    entry.read_to_end(&mut read).unwrap();
+    assert_eq!(read, data);
+
+    assert_eq!(entry.seek(SeekFrom::Start(1000)).unwrap(), 1000);
+    let mut read = Vec::new();
+    entry.read_to_end(&mut read).unwrap();
+    assert_eq!(read, &data[1000..]);
+}
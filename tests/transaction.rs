@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use libzip_sys::{DropPolicy, EntryOptions, ZipError, ZipFile};
+
+/// An archive on disk holding `original.txt`, removed again on drop.
+struct Archive(PathBuf);
+
+impl Archive {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("libzip-sys-{}-{name}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut zip = ZipFile::open(&path, true).unwrap();
+        zip.add_bytes(b"original".to_vec(), "original.txt", &EntryOptions::new())
+            .unwrap();
+        zip.close().unwrap();
+
+        Self(path)
+    }
+
+    fn open(&self) -> ZipFile {
+        ZipFile::open(&self.0, false).unwrap()
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        fs::read(&self.0).unwrap()
+    }
+}
+
+impl Drop for Archive {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn transaction_commits_on_ok() {
+    let archive = Archive::new("transaction-ok.zip");
+
+    let mut zip = archive.open();
+    let value = zip
+        .transaction(|zip| {
+            zip.add_bytes(b"added".to_vec(), "added.txt", &EntryOptions::new())?;
+            Ok(7)
+        })
+        .unwrap();
+    assert_eq!(value, 7);
+    assert_eq!(zip.len(), 0);
+
+    let zip = archive.open();
+    assert!(zip.get_entry("original.txt", false).is_some());
+    assert!(zip.get_entry("added.txt", false).is_some());
+}
+
+#[test]
+fn transaction_error_leaves_the_file_untouched() {
+    let archive = Archive::new("transaction-err.zip");
+    let before = archive.bytes();
+
+    let mut zip = archive.open();
+    let result: Result<(), _> = zip.transaction(|zip| {
+        zip.add_bytes(b"added".to_vec(), "added.txt", &EntryOptions::new())?;
+        zip.delete_file("original.txt")?;
+        Err(ZipError::Invalid)
+    });
+    assert!(matches!(result, Err(ZipError::Invalid)));
+    assert!(matches!(
+        zip.add_bytes(b"late".to_vec(), "late.txt", &EntryOptions::new()),
+        Err(ZipError::NotOpen)
+    ));
+
+    assert_eq!(archive.bytes(), before);
+}
+
+#[test]
+fn transaction_closes_the_archive_when_the_commit_fails() {
+    let archive = Archive::new("transaction-commit-fails.zip");
+    let before = archive.bytes();
+
+    let mut zip = archive.open();
+    zip.set_cancel_token(Arc::new(AtomicBool::new(true)))
+        .unwrap();
+    let result =
+        zip.transaction(|zip| zip.add_bytes(b"added".to_vec(), "added.txt", &EntryOptions::new()));
+    assert!(matches!(result, Err(ZipError::Cancelled)));
+    assert!(matches!(
+        zip.add_bytes(b"late".to_vec(), "late.txt", &EntryOptions::new()),
+        Err(ZipError::NotOpen)
+    ));
+
+    drop(zip);
+    assert_eq!(archive.bytes(), before);
+}
+
+#[test]
+#[should_panic(expected = "uncommitted changes")]
+fn panic_if_dirty_panics_on_uncommitted_changes() {
+    let mut zip = ZipFile::new_in_memory().unwrap();
+    zip.set_drop_policy(DropPolicy::PanicIfDirty);
+    zip.add_bytes(b"data".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+
+    drop(zip);
+}
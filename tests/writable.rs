@@ -0,0 +1,82 @@
+use std::fs;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use libzip_sys::{Compression, EntryOptions, SetLen, ZipError, ZipFile};
+
+/// A destination that can be read and seeked but refuses every write with an
+/// error that has no OS error code.
+struct FullSink(Cursor<Vec<u8>>);
+
+impl Read for FullSink {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for FullSink {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::other("sink is full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for FullSink {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl SetLen for FullSink {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.0.set_len(len)
+    }
+}
+
+#[test]
+fn close_keeps_the_sink_error() {
+    let mut zip = ZipFile::options()
+        .create()
+        .open_writable(FullSink(Cursor::new(Vec::new())))
+        .unwrap();
+    zip.add_bytes(b"data".to_vec(), "data.txt", &EntryOptions::new())
+        .unwrap();
+
+    match zip.close() {
+        Err(ZipError::Write(Some(error))) => assert_eq!(error.to_string(), "sink is full"),
+        result => panic!("unexpected result: {result:?}"),
+    }
+    zip.discard();
+}
+
+#[test]
+fn large_archives_are_staged_on_disk() {
+    let path = std::env::temp_dir().join(format!("libzip-sys-{}-writable.zip", std::process::id()));
+    let target = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+
+    // Larger than what is staged in memory, and stored so it stays that large.
+    let data: Vec<u8> = (0..24 * 1024 * 1024).map(|i| (i % 253) as u8).collect();
+    let mut zip = ZipFile::options().create().open_writable(target).unwrap();
+    let options = EntryOptions::new().compression(Compression::Store);
+    zip.add_bytes(data.clone(), "large.bin", &options).unwrap();
+    zip.close().unwrap();
+
+    let zip = ZipFile::open(&path, false).unwrap();
+    let mut read = Vec::new();
+    zip.get_entry("large.bin", true)
+        .unwrap()
+        .read_to_end(&mut read)
+        .unwrap();
+    assert!(read == data);
+
+    drop(zip);
+    fs::remove_file(&path).unwrap();
+}